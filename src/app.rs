@@ -1,5 +1,8 @@
 use crate::config::ArgEnum;
 use crate::config::{DarkSkyUnit, GenericWeatherUnit, OwmUnit};
+use crate::output::{Bar, Format};
+use crate::weather::{DistanceUnit, PrecipitationUnit, PressureUnit, SpeedUnit, TemperatureUnit};
+use crate::weather_api::check_base_url;
 use clap::{App, Arg, SubCommand};
 
 pub fn build_cli() -> App<'static, 'static> {
@@ -9,7 +12,43 @@ pub fn build_cli() -> App<'static, 'static> {
                 .long("units")
                 .short("u")
                 .takes_value(true)
-                .possible_values(GenericWeatherUnit::VARIANTS),
+                .possible_values(GenericWeatherUnit::VARIANTS)
+                .help("The unit system to ask for and show, over [display] in the config"),
+        )
+        .arg(
+            Arg::with_name("temperature-unit")
+                .long("temperature-unit")
+                .takes_value(true)
+                .possible_values(TemperatureUnit::VARIANTS)
+                .help("Show temperatures in this unit, over --units and [display]"),
+        )
+        .arg(
+            Arg::with_name("speed-unit")
+                .long("speed-unit")
+                .takes_value(true)
+                .possible_values(SpeedUnit::VARIANTS)
+                .help("Show wind speeds in this unit, over --units and [display]"),
+        )
+        .arg(
+            Arg::with_name("pressure-unit")
+                .long("pressure-unit")
+                .takes_value(true)
+                .possible_values(PressureUnit::VARIANTS)
+                .help("Show pressure in this unit, over --units and [display]"),
+        )
+        .arg(
+            Arg::with_name("distance-unit")
+                .long("distance-unit")
+                .takes_value(true)
+                .possible_values(DistanceUnit::VARIANTS)
+                .help("Show visibility in this unit, over --units and [display]"),
+        )
+        .arg(
+            Arg::with_name("precipitation-unit")
+                .long("precipitation-unit")
+                .takes_value(true)
+                .possible_values(PrecipitationUnit::VARIANTS)
+                .help("Show precipitation in this unit, over --units and [display]"),
         )
         .arg(
             Arg::with_name("coordinates")
@@ -24,29 +63,229 @@ pub fn build_cli() -> App<'static, 'static> {
             Arg::with_name("time")
                 .long("time")
                 .short("t")
-                .takes_value(true),
+                .takes_value(true)
+                .help("Show historical data for a time, from DarkSky unless another provider is given, e.g. \"yesterday 15:00\", \"-3d\" or 2018-11-13T15:00"),
         )
         .arg(Arg::with_name("live").long("live"))
+        .arg(
+            Arg::with_name("no-cache")
+                .long("no-cache")
+                .help("Don't read or write cached responses"),
+        )
+        .arg(
+            Arg::with_name("refresh")
+                .long("refresh")
+                .conflicts_with("no-cache")
+                .help("Ignore cached responses, but cache the new ones"),
+        )
+        .arg(
+            Arg::with_name("timeout")
+                .long("timeout")
+                .takes_value(true)
+                .value_name("seconds")
+                .help("How long the providers have to answer, all together"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .short("f")
+                .takes_value(true)
+                .possible_values(Format::VARIANTS),
+        )
+        .arg(
+            Arg::with_name("bar")
+                .long("bar")
+                .takes_value(true)
+                .possible_values(Bar::VARIANTS)
+                .help("The status bar to print for, with --format=bar"),
+        )
+        .arg(
+            Arg::with_name("template")
+                .long("template")
+                .takes_value(true)
+                .conflicts_with("format")
+                .help("Print using a template, e.g. \"{icon} {temp:.0}{unit} {summary}\""),
+        )
         .subcommand(
             SubCommand::with_name("owm")
                 .about("OpenWeatherMap")
                 .arg(Arg::with_name("current").long("current").short("c"))
+                .arg(
+                    Arg::with_name("forecast")
+                        .long("forecast")
+                        .conflicts_with("current")
+                        .help("Show the 5 day, 3 hour forecast"),
+                )
+                .arg(
+                    Arg::with_name("one-call")
+                        .long("one-call")
+                        .help("Use the One Call API, which needs coordinates"),
+                )
                 .arg(
                     Arg::with_name("units")
                         .long("units")
                         .short("u")
                         .takes_value(true)
                         .possible_values(OwmUnit::VARIANTS),
+                )
+                .arg(
+                    Arg::with_name("base-url")
+                        .long("base-url")
+                        .takes_value(true)
+                        .validator(|url| check_base_url(&url))
+                        .help("Send requests here instead, e.g. http://localhost:8080"),
                 ),
         )
         .subcommand(
-            SubCommand::with_name("darksky").about("DarkSky").arg(
-                Arg::with_name("units")
-                    .long("units")
-                    .short("u")
-                    .takes_value(true)
-                    .possible_values(DarkSkyUnit::VARIANTS),
-            ),
+            SubCommand::with_name("darksky")
+                .about("DarkSky")
+                .arg(
+                    Arg::with_name("units")
+                        .long("units")
+                        .short("u")
+                        .takes_value(true)
+                        .possible_values(DarkSkyUnit::VARIANTS),
+                )
+                .arg(
+                    Arg::with_name("base-url")
+                        .long("base-url")
+                        .takes_value(true)
+                        .validator(|url| check_base_url(&url))
+                        .help("Send requests here instead, e.g. http://localhost:8080"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("openmeteo")
+                .about("Open-Meteo")
+                .arg(
+                    Arg::with_name("forecast")
+                        .long("forecast")
+                        .help("Show the hourly forecast"),
+                )
+                .arg(
+                    Arg::with_name("units")
+                        .long("units")
+                        .short("u")
+                        .takes_value(true)
+                        .possible_values(GenericWeatherUnit::VARIANTS),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("eccc")
+                .about("Environment and Climate Change Canada")
+                .arg(
+                    Arg::with_name("forecast")
+                        .long("forecast")
+                        .help("Show the forecast, by day"),
+                )
+                .arg(
+                    Arg::with_name("site")
+                        .long("site")
+                        .takes_value(true)
+                        .help("The citypage to use, e.g. MB/s0000193"),
+                )
+                .arg(
+                    Arg::with_name("units")
+                        .long("units")
+                        .short("u")
+                        .takes_value(true)
+                        .possible_values(GenericWeatherUnit::VARIANTS),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("metno")
+                .about("MET Norway's Locationforecast")
+                .arg(
+                    Arg::with_name("forecast")
+                        .long("forecast")
+                        .help("Show the forecast, by day and by hour"),
+                )
+                .arg(
+                    Arg::with_name("complete")
+                        .long("complete")
+                        .help("Ask for the complete forecast, with probabilities and dew points"),
+                )
+                .arg(
+                    Arg::with_name("units")
+                        .long("units")
+                        .short("u")
+                        .takes_value(true)
+                        .possible_values(GenericWeatherUnit::VARIANTS),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("nws")
+                .about("The US National Weather Service")
+                .arg(
+                    Arg::with_name("forecast")
+                        .long("forecast")
+                        .help("Show the forecast, by day and by hour"),
+                )
+                .arg(
+                    Arg::with_name("units")
+                        .long("units")
+                        .short("u")
+                        .takes_value(true)
+                        .possible_values(GenericWeatherUnit::VARIANTS),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("pirateweather")
+                .about("Pirate Weather")
+                .arg(
+                    Arg::with_name("forecast")
+                        .long("forecast")
+                        .help("Show the forecast, by day and by hour"),
+                )
+                .arg(
+                    Arg::with_name("units")
+                        .long("units")
+                        .short("u")
+                        .takes_value(true)
+                        .possible_values(DarkSkyUnit::VARIANTS),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("compare")
+                .about("Compare the current conditions from every configured provider"),
+        )
+        .subcommand(
+            SubCommand::with_name("usage")
+                .about("Show how many calls have been made to each provider today and this month"),
+        )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("Save DarkSky's historical data for a range of days as CSV")
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The first day, e.g. 2018-06-01 or -30d"),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The last day, e.g. 2018-08-31 or yesterday"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .short("o")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The CSV file to write; days already in it are skipped"),
+                )
+                .arg(
+                    Arg::with_name("jobs")
+                        .long("jobs")
+                        .short("j")
+                        .takes_value(true)
+                        .default_value("4")
+                        .help("How many days to fetch at once"),
+                ),
         )
         .arg(
             Arg::with_name("verbose")
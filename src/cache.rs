@@ -0,0 +1,316 @@
+//! On-disk cache of API responses, under `$XDG_CACHE_HOME/nimbus-alt`.
+
+use crate::config::{CacheConfig, Config};
+use clap::ArgMatches;
+use failure::Error;
+use serde_derive::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The kind of data an endpoint returns, which decides how long it stays fresh.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    Current,
+    Forecast,
+    Historical,
+}
+
+#[derive(Debug)]
+pub struct Cache<'a> {
+    dir: PathBuf,
+    ttl: &'a CacheConfig,
+    /// Ignore what's cached, but still store fresh responses.
+    refresh: bool,
+}
+
+/// A cached response body.
+#[derive(Debug, PartialEq)]
+pub struct Cached {
+    pub body: String,
+    /// Seconds since the response was fetched.
+    pub age: u64,
+    /// The response's `Last-Modified` header, to ask the API whether it has changed since.
+    pub last_modified: Option<String>,
+}
+
+/// What a response's headers said about caching it.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Validity {
+    /// When the response stops being fresh (`Expires`), in seconds since the epoch.
+    pub expires: Option<u64>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Entry {
+    key: String,
+    fetched_at: u64,
+    body: String,
+    expires: Option<u64>,
+    last_modified: Option<String>,
+}
+
+impl<'a> Cache<'a> {
+    /// Returns `None` when caching is turned off with `--no-cache`, or there's no cache directory.
+    pub fn new(config: &'a Config, m: &ArgMatches) -> Option<Self> {
+        static DEFAULT_TTL: CacheConfig = CacheConfig {
+            current: None,
+            forecast: None,
+            historical: None,
+        };
+
+        if m.is_present("no-cache") {
+            return None;
+        }
+
+        let dir = match dirs::cache_dir() {
+            Some(dir) => dir.join(crate_name!()),
+            None => {
+                warn!("couldn't find XDG_CACHE_HOME, not caching responses");
+                return None;
+            }
+        };
+
+        Some(Self {
+            dir,
+            ttl: config.cache.as_ref().unwrap_or(&DEFAULT_TTL),
+            refresh: m.is_present("refresh"),
+        })
+    }
+
+    /// The cached response for `key`, if it's still fresh: while it's younger than the endpoint's
+    /// TTL, or until it expires when that's later.
+    ///
+    /// Only responses from providers that ask not to be fetched again before `Expires` have one
+    /// (see [`Provider::follows_expires`](crate::weather_api::Provider::follows_expires)), so the
+    /// TTLs are never cut short.
+    pub fn get(&self, endpoint: Endpoint, key: &str) -> Option<Cached> {
+        if self.refresh {
+            return None;
+        }
+
+        let entry = self.read(key)?;
+        let fresh = Duration::from_secs(now().saturating_sub(entry.fetched_at))
+            <= self.ttl.ttl(endpoint)
+            || entry.expires.is_some_and(|expires| now() < expires);
+
+        let cached = Cached::from(entry);
+        if !fresh {
+            debug!(
+                "cached response for {} is {}s old and expired, ignoring it",
+                key, cached.age
+            );
+            return None;
+        }
+
+        debug!("using cached response for {} ({}s old)", key, cached.age);
+        Some(cached)
+    }
+
+    /// The most recent response for `key`, however old it is.
+    pub fn get_stale(&self, key: &str) -> Option<Cached> {
+        self.read(key).map(Cached::from)
+    }
+
+    pub fn put(&self, key: &str, body: &str, validity: Validity) -> Result<(), Error> {
+        fs::create_dir_all(&self.dir)?;
+
+        let entry = Entry {
+            key: key.to_string(),
+            fetched_at: now(),
+            body: body.to_string(),
+            expires: validity.expires,
+            last_modified: validity.last_modified,
+        };
+        fs::write(self.path(key), serde_json::to_string(&entry)?)?;
+
+        Ok(())
+    }
+
+    /// Mark the cached response for `key` as fetched just now, when the API says it hasn't
+    /// changed.
+    pub fn touch(&self, key: &str, validity: Validity) -> Result<(), Error> {
+        match self.read(key) {
+            Some(entry) => self.put(
+                key,
+                &entry.body,
+                Validity {
+                    last_modified: validity.last_modified.or(entry.last_modified),
+                    ..validity
+                },
+            ),
+            None => Ok(()),
+        }
+    }
+
+    fn read(&self, key: &str) -> Option<Entry> {
+        let contents = fs::read_to_string(self.path(key)).ok()?;
+        serde_json::from_str::<Entry>(&contents)
+            .ok()
+            // Guard against hash collisions.
+            .filter(|entry| entry.key == key)
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        // FNV-1a, since `DefaultHasher` isn't guaranteed to give the same hash between builds.
+        let hash = key.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+
+        self.dir.join(format!("{:016x}.json", hash))
+    }
+}
+
+/// The cache key for a request: its URL, with the API key taken out.
+pub fn key(url: &str, secret: Option<&str>) -> String {
+    match secret {
+        Some(secret) if !secret.is_empty() => url.replace(secret, "{key}"),
+        _ => url.to_string(),
+    }
+}
+
+impl From<Entry> for Cached {
+    fn from(entry: Entry) -> Self {
+        Self {
+            age: now().saturating_sub(entry.fetched_at),
+            body: entry.body,
+            last_modified: entry.last_modified,
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache<'a>(name: &str, ttl: &'a CacheConfig) -> Cache<'a> {
+        let dir = std::env::temp_dir().join(format!("{}-{}", crate_name!(), name));
+        let _ = fs::remove_dir_all(&dir);
+
+        Cache {
+            dir,
+            ttl,
+            refresh: false,
+        }
+    }
+
+    #[test]
+    fn it_takes_the_api_key_out_of_the_key() {
+        assert_eq!(
+            "https://api.darksky.net/forecast/{key}/12.345,-54.321",
+            key(
+                "https://api.darksky.net/forecast/my_key/12.345,-54.321",
+                Some("my_key")
+            )
+        );
+        assert_eq!(
+            "https://api.openweathermap.org/data/2.5/weather?appid={key}&id=1",
+            key(
+                "https://api.openweathermap.org/data/2.5/weather?appid=owm_key&id=1",
+                Some("owm_key")
+            )
+        );
+    }
+
+    #[test]
+    fn it_returns_fresh_entries() {
+        let ttl = CacheConfig::default();
+        let cache = cache("fresh", &ttl);
+
+        assert_eq!(None, cache.get(Endpoint::Current, "a"));
+        cache.put("a", "{}", Validity::default()).unwrap();
+        assert_eq!(
+            Some(String::from("{}")),
+            cache.get(Endpoint::Current, "a").map(|cached| cached.body)
+        );
+        assert_eq!(None, cache.get(Endpoint::Current, "b"));
+    }
+
+    #[test]
+    fn it_ignores_expired_entries() {
+        let ttl = CacheConfig::default();
+        let cache = cache("expired", &ttl);
+        let entry = Entry {
+            key: String::from("a"),
+            fetched_at: now() - 11 * 60,
+            body: String::from("{}"),
+            expires: None,
+            last_modified: None,
+        };
+        fs::create_dir_all(&cache.dir).unwrap();
+        fs::write(cache.path("a"), serde_json::to_string(&entry).unwrap()).unwrap();
+
+        assert_eq!(None, cache.get(Endpoint::Current, "a"));
+        assert_eq!(
+            Some(String::from("{}")),
+            cache.get(Endpoint::Forecast, "a").map(|cached| cached.body)
+        );
+        assert_eq!(Some(11 * 60), cache.get_stale("a").map(|cached| cached.age));
+    }
+
+    #[test]
+    fn it_keeps_entries_until_the_ttl_and_expires_have_both_passed() {
+        let ttl = CacheConfig::default();
+        let cache = cache("expires", &ttl);
+        let write = |fetched_at, expires| {
+            let entry = Entry {
+                key: String::from("a"),
+                fetched_at,
+                body: String::from("{}"),
+                expires,
+                last_modified: Some(String::from("Tue, 13 Nov 2018 15:00:00 GMT")),
+            };
+            fs::create_dir_all(&cache.dir).unwrap();
+            fs::write(cache.path("a"), serde_json::to_string(&entry).unwrap()).unwrap();
+        };
+
+        // Past the 10 minute TTL, but the API asked not to be fetched again yet.
+        write(now() - 11 * 60, Some(now() + 60));
+        assert!(cache.get(Endpoint::Current, "a").is_some());
+
+        // Past both.
+        write(now() - 11 * 60, Some(now() - 1));
+        assert_eq!(None, cache.get(Endpoint::Current, "a"));
+        assert_eq!(
+            Some(String::from("Tue, 13 Nov 2018 15:00:00 GMT")),
+            cache.get_stale("a").and_then(|cached| cached.last_modified)
+        );
+
+        // An earlier `Expires` doesn't cut the TTL short.
+        write(now(), Some(now() - 1));
+        assert!(cache.get(Endpoint::Historical, "a").is_some());
+
+        write(now() - 11 * 60, Some(now() - 1));
+        cache
+            .touch(
+                "a",
+                Validity {
+                    expires: Some(now() + 60),
+                    last_modified: None,
+                },
+            )
+            .unwrap();
+        let cached = cache.get(Endpoint::Current, "a").unwrap();
+        assert_eq!("{}", cached.body);
+        assert!(cached.last_modified.is_some());
+    }
+
+    #[test]
+    fn it_skips_the_cache_when_refreshing() {
+        let ttl = CacheConfig::default();
+        let mut cache = cache("refresh", &ttl);
+        cache.put("a", "{}", Validity::default()).unwrap();
+        cache.refresh = true;
+
+        assert_eq!(None, cache.get(Endpoint::Current, "a"));
+        assert!(cache.get_stale("a").is_some());
+    }
+}
@@ -1,14 +1,34 @@
+use crate::weather_api::{check_base_url, Provider};
 use crate::Error;
+use chrono_tz::Tz;
 use failure::{Fail, ResultExt};
+use reqwest::header::HeaderValue;
 use serde_derive::Deserialize;
 use std::fs::File;
 use std::io::prelude::*;
+use strum::IntoEnumIterator;
 
+mod cache_config;
 mod darksky_config;
+mod display_config;
+mod eccc_config;
+mod metno_config;
+mod nws_config;
+mod openmeteo_config;
 mod owm_config;
+mod pirateweather_config;
+mod retry_config;
 
+pub use self::cache_config::*;
 pub use self::darksky_config::*;
+pub use self::display_config::*;
+pub use self::eccc_config::*;
+pub use self::metno_config::*;
+pub use self::nws_config::*;
+pub use self::openmeteo_config::*;
 pub use self::owm_config::*;
+pub use self::pirateweather_config::*;
+pub use self::retry_config::*;
 
 #[derive(Debug, Deserialize, EnumString, EnumIter, Copy, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -43,6 +63,16 @@ pub struct InvalidUnit;
 ///
 /// coordinates = [ 12.345, -54.321 ]
 /// unit = "metric"
+/// timezone = "America/Winnipeg"
+/// template = "{icon} {temp:.0}{unit} {summary}"
+/// providers = [ "darksky", "owm" ]
+/// timeout = 10
+///
+/// ## Shown in these units, whatever the providers answer in.
+/// [display]
+/// temperature = "celsius"
+/// speed = "kilometres_per_hour"
+/// pressure = "hectopascals"
 ///
 /// [owm]
 /// key = "a1b2c3d4"
@@ -52,17 +82,67 @@ pub struct InvalidUnit;
 /// [darksky]
 /// key = "n1o2p3q4"
 /// unit = "ca"
+/// base_url = "http://localhost:8080"
+/// daily_budget = 1000
+///
+/// [pirateweather]
+/// key = "r1s2t3u4"
+/// unit = "ca"
+///
+/// [openmeteo]
+/// unit = "metric"
+///
+/// [nws]
+/// user_agent = "(nimbus-alt, you@example.com)"
+///
+/// [metno]
+/// user_agent = "nimbus-alt you@example.com"
+/// complete = true
+///
+/// [eccc]
+/// site = "MB/s0000193"
+///
+/// ## How long responses are cached for, in seconds.
+/// [cache]
+/// current = 600
+/// forecast = 3600
+/// historical = 2592000
+///
+/// ## How failed requests are tried again.
+/// [retry]
+/// attempts = 3
+/// backoff = 500
+/// max_backoff = 10000
 /// # "#)
 /// # .expect("failed to parse example config.toml");
 /// # }
 /// ```
 #[derive(Debug, Deserialize, PartialEq)]
+#[cfg_attr(test, derive(Default))]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     pub coordinates: Option<(f64, f64)>,
     pub unit: Option<GenericWeatherUnit>,
+    /// The time zone `--time` is in, e.g. `America/Winnipeg`. Defaults to the one DarkSky gives
+    /// for the coordinates.
+    pub timezone: Option<Tz>,
+    /// Replaces the default text output. See [`Template`](crate::output::Template).
+    pub template: Option<String>,
+    /// The providers to try, in order, when no subcommand picks one. Each is only tried when the
+    /// ones before it failed. Without it, both DarkSky and OpenWeatherMap are shown.
+    pub providers: Option<Vec<Provider>>,
+    /// How long the providers have to answer, in seconds, all together. Defaults to 30.
+    pub timeout: Option<u64>,
+    pub display: Option<DisplayConfig>,
+    pub cache: Option<CacheConfig>,
+    pub retry: Option<RetryConfig>,
     pub darksky: Option<DarkSkyConfig>,
     pub owm: Option<OwmConfig>,
+    pub pirateweather: Option<PirateWeatherConfig>,
+    pub openmeteo: Option<OpenMeteoConfig>,
+    pub nws: Option<NwsConfig>,
+    pub metno: Option<MetNoConfig>,
+    pub eccc: Option<EcccConfig>,
 }
 
 impl Config {
@@ -78,19 +158,117 @@ impl Config {
         file.read_to_string(&mut contents)?;
 
         let config: Self = toml::from_str(&contents)?;
+        config.check()?;
 
+        Ok(config)
+    }
+
+    /// What parsing can't catch, like a provider with no location to ask about.
+    fn check(&self) -> Result<(), Error> {
         // Ensure that if the OWM config is specified, that there is always a location.
-        if let Some(owm) = &config.owm {
-            if owm.location_id.is_none() && config.coordinates.is_none() {
+        if let Some(owm) = &self.owm {
+            if owm.location_id.is_none() && self.coordinates.is_none() {
                 return Err(LocationMissingError.into());
             }
         }
 
-        if config.darksky.is_none() && config.coordinates.is_none() {
+        if self.darksky.is_none() && self.coordinates.is_none() {
             return Err(LocationMissingError.into());
         }
 
-        Ok(config)
+        // Catch these now, instead of when the provider's turn comes.
+        if let Some(provider) = self
+            .providers
+            .iter()
+            .flatten()
+            .find(|&&provider| !self.is_configured(provider))
+        {
+            return Err(ProviderNotConfiguredError(*provider).into());
+        }
+        // Otherwise the fallback would stop at it, instead of moving on to the next one.
+        if let Some(provider) = self
+            .providers
+            .iter()
+            .flatten()
+            .find(|&&provider| !self.is_located(provider))
+        {
+            return Err(ProviderLocationMissingError(*provider).into());
+        }
+
+        let base_urls = [
+            (
+                "darksky",
+                self.darksky
+                    .as_ref()
+                    .and_then(|darksky| darksky.base_url.as_ref()),
+            ),
+            (
+                "owm",
+                self.owm.as_ref().and_then(|owm| owm.base_url.as_ref()),
+            ),
+        ];
+        for (section, base_url) in &base_urls {
+            if let Some(base_url) = base_url {
+                check_base_url(base_url).map_err(|e| InvalidBaseUrlError(section, e))?;
+            }
+        }
+
+        // Without a `User-Agent`, both of them answer 403 Forbidden.
+        if let Some(nws) = &self.nws {
+            check_user_agent("nws", &nws.user_agent)?;
+        }
+        if let Some(user_agent) = self
+            .metno
+            .as_ref()
+            .and_then(|metno| metno.user_agent.as_ref())
+        {
+            check_user_agent("metno", user_agent)?;
+        }
+
+        Ok(())
+    }
+
+    /// The `providers`, or else every provider that has a section. One Call is left out unless it's
+    /// listed, since it needs its own subscription.
+    pub fn configured_providers(&self) -> Vec<Provider> {
+        if let Some(providers) = &self.providers {
+            return providers.clone();
+        }
+
+        Provider::iter()
+            .filter(|&provider| match provider {
+                Provider::DarkSky => self.darksky.is_some(),
+                Provider::Eccc => self.eccc.is_some(),
+                Provider::MetNo => self.metno.is_some(),
+                Provider::Nws => self.nws.is_some(),
+                Provider::OpenMeteo => self.openmeteo.is_some(),
+                Provider::Owm => self.owm.is_some(),
+                Provider::OwmOneCall => false,
+                Provider::PirateWeather => self.pirateweather.is_some(),
+            })
+            .collect()
+    }
+
+    /// Whether the provider's section is there, for the ones that need one.
+    pub fn is_configured(&self, provider: Provider) -> bool {
+        match provider {
+            Provider::DarkSky => self.darksky.is_some(),
+            Provider::Owm | Provider::OwmOneCall => self.owm.is_some(),
+            Provider::PirateWeather => self.pirateweather.is_some(),
+            Provider::Nws => self.nws.is_some(),
+            Provider::Eccc | Provider::MetNo | Provider::OpenMeteo => true,
+        }
+    }
+
+    /// Whether the config says where to ask the provider about. Only OWM can do without
+    /// `coordinates`, with a `location_id`; One Call can't.
+    fn is_located(&self, provider: Provider) -> bool {
+        self.coordinates.is_some()
+            || provider == Provider::Owm
+                && self
+                    .owm
+                    .as_ref()
+                    .is_some_and(|owm| owm.location_id.is_some())
     }
 }
 
@@ -101,41 +279,41 @@ impl Config {
 )]
 pub struct LocationMissingError;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use strum::IntoEnumIterator;
-
-    impl Default for Config {
-        fn default() -> Self {
-            Self {
-                coordinates: None,
-                unit: None,
-                owm: None,
-                darksky: None,
-            }
-        }
-    }
+#[derive(Fail, Debug)]
+#[fail(display = "{} is one of the `providers`, but isn't configured.", _0)]
+pub struct ProviderNotConfiguredError(Provider);
 
-    impl Default for OwmConfig {
-        fn default() -> Self {
-            Self {
-                key: String::new(),
-                location_id: None,
-                unit: None,
-            }
-        }
-    }
+#[derive(Fail, Debug)]
+#[fail(
+    display = "{} is one of the `providers`, but there are no `coordinates` for it.",
+    _0
+)]
+pub struct ProviderLocationMissingError(Provider);
 
-    impl Default for DarkSkyConfig {
-        fn default() -> Self {
-            Self {
-                key: String::new(),
-                unit: None,
-            }
-        }
+#[derive(Fail, Debug)]
+#[fail(display = "[{}] {}", _0, _1)]
+pub struct InvalidBaseUrlError(&'static str, String);
+
+#[derive(Fail, Debug)]
+#[fail(
+    display = "The `user_agent` in [{}] can't be sent as a header: {:?}. Use something like \
+               \"(nimbus-alt, you@example.com)\".",
+    _0, _1
+)]
+pub struct InvalidUserAgentError(&'static str, String);
+
+fn check_user_agent(section: &'static str, user_agent: &str) -> Result<(), InvalidUserAgentError> {
+    if user_agent.trim().is_empty() || HeaderValue::from_str(user_agent).is_err() {
+        return Err(InvalidUserAgentError(section, user_agent.to_string()));
     }
 
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
     macro_rules! test_variants {
         ($name:ident, $t:ident) => {
             #[test]
@@ -153,4 +331,109 @@ mod tests {
     test_variants!(generic_unit_variants, GenericWeatherUnit);
     test_variants!(owm_unit_variants, OwmUnit);
     test_variants!(darksky_unit_variants, DarkSkyUnit);
+
+    #[test]
+    fn it_reads_the_providers_in_order() {
+        let config: Config = toml::from_str(
+            r#"
+            providers = ["owm-onecall", "darksky", "metno"]
+
+            [darksky]
+            key = "n1o2p3q4"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(vec![
+                Provider::OwmOneCall,
+                Provider::DarkSky,
+                Provider::MetNo
+            ]),
+            config.providers
+        );
+        assert!(!config.is_configured(Provider::OwmOneCall));
+        assert!(config.is_configured(Provider::DarkSky));
+        assert!(config.is_configured(Provider::MetNo));
+    }
+
+    #[test]
+    fn it_compares_every_provider_with_a_section() {
+        let config: Config = toml::from_str(
+            r#"
+            [owm]
+            key = "a1b2c3d4"
+
+            [darksky]
+            key = "n1o2p3q4"
+
+            [metno]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![Provider::DarkSky, Provider::MetNo, Provider::Owm],
+            config.configured_providers()
+        );
+    }
+
+    #[test]
+    fn it_rejects_user_agents_that_cant_be_sent() {
+        let config = |section: &str| {
+            toml::from_str::<Config>(&format!("coordinates = [49.9, -97.14]\n{}", section)).unwrap()
+        };
+
+        assert!(
+            config("[nws]\nuser_agent = \"(nimbus-alt, you@example.com)\"")
+                .check()
+                .is_ok()
+        );
+        assert!(config("[nws]\nuser_agent = \"\"").check().is_err());
+        assert!(config("[nws]\nuser_agent = \"a\\nb\"").check().is_err());
+        assert!(config("[metno]\nuser_agent = \" \"").check().is_err());
+        assert!(config("[metno]").check().is_ok());
+    }
+
+    #[test]
+    fn it_rejects_base_urls_without_a_path() {
+        let config = |base_url: &str| {
+            let config = format!("[darksky]\nkey = \"n1o2p3q4\"\nbase_url = \"{}\"", base_url);
+            toml::from_str::<Config>(&config).unwrap()
+        };
+
+        assert!(config("http://localhost:8080").check().is_ok());
+        assert!(config("localhost:8080").check().is_err());
+        assert!(config("not a url").check().is_err());
+    }
+
+    #[test]
+    fn it_rejects_providers_without_a_location() {
+        let config: Config = toml::from_str(
+            r#"
+            providers = ["owm", "metno"]
+
+            [owm]
+            key = "a1b2c3d4"
+            location_id = "1234567"
+
+            [darksky]
+            key = "n1o2p3q4"
+            "#,
+        )
+        .unwrap();
+        assert!(config.check().is_err());
+
+        let config = Config {
+            providers: Some(vec![Provider::DarkSky]),
+            ..config
+        };
+        assert!(config.check().is_err());
+
+        let config = Config {
+            providers: Some(vec![Provider::Owm]),
+            ..config
+        };
+        assert!(config.check().is_ok());
+    }
 }
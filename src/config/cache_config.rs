@@ -0,0 +1,26 @@
+use crate::cache::Endpoint;
+use serde_derive::Deserialize;
+use std::time::Duration;
+
+/// How long responses stay fresh in the cache, in seconds, per kind of endpoint.
+#[derive(Debug, Deserialize, PartialEq)]
+#[cfg_attr(test, derive(Default))]
+#[serde(deny_unknown_fields)]
+pub struct CacheConfig {
+    pub current: Option<u64>,
+    pub forecast: Option<u64>,
+    pub historical: Option<u64>,
+}
+
+impl CacheConfig {
+    pub fn ttl(&self, endpoint: Endpoint) -> Duration {
+        let seconds = match endpoint {
+            Endpoint::Current => self.current.unwrap_or(10 * 60),
+            Endpoint::Forecast => self.forecast.unwrap_or(60 * 60),
+            // The past doesn't change much.
+            Endpoint::Historical => self.historical.unwrap_or(30 * 24 * 60 * 60),
+        };
+
+        Duration::from_secs(seconds)
+    }
+}
@@ -3,10 +3,17 @@ use super::GenericWeatherUnit;
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, PartialEq)]
+#[cfg_attr(test, derive(Default))]
 #[serde(deny_unknown_fields)]
 pub struct DarkSkyConfig {
     pub key: String,
     pub unit: Option<DarkSkyUnit>,
+    /// Send requests here instead, e.g. to a caching proxy or a mock server. It replaces
+    /// `https://api.darksky.net`, and the paths under it stay the same.
+    pub base_url: Option<String>,
+    /// How many calls a day are allowed. After that, only cached responses are used until the
+    /// next day (in UTC).
+    pub daily_budget: Option<u64>,
 }
 
 #[derive(
@@ -18,6 +25,8 @@ pub enum DarkSkyUnit {
     Auto,
     Ca,
     Si,
+    // Pirate Weather calls it `uk`.
+    #[serde(alias = "uk")]
     Uk2,
     Us,
 }
@@ -0,0 +1,24 @@
+use crate::weather::{DistanceUnit, PrecipitationUnit, PressureUnit, SpeedUnit, TemperatureUnit};
+use serde_derive::Deserialize;
+
+/// The unit to show each kind of measurement in, no matter which unit the provider answered in.
+///
+/// For each kind, the first of these that's set wins:
+///
+/// 1. its flag, e.g. `--speed-unit`
+/// 2. `--units`
+/// 3. this section
+/// 4. `unit`
+/// 5. the unit the provider answered in (Celsius instead of Kelvin)
+///
+/// So `--units imperial` on the command line replaces this section for one run, and a flag like
+/// `--speed-unit` replaces both. `metric` is m/s for wind, like OWM's; pick km/h here.
+#[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DisplayConfig {
+    pub temperature: Option<TemperatureUnit>,
+    pub speed: Option<SpeedUnit>,
+    pub pressure: Option<PressureUnit>,
+    pub distance: Option<DistanceUnit>,
+    pub precipitation: Option<PrecipitationUnit>,
+}
@@ -0,0 +1,13 @@
+use super::GenericWeatherUnit;
+use serde_derive::Deserialize;
+
+/// Environment Canada doesn't need a key, so this whole section is optional.
+#[derive(Debug, Deserialize, PartialEq)]
+#[cfg_attr(test, derive(Default))]
+#[serde(deny_unknown_fields)]
+pub struct EcccConfig {
+    /// The citypage to use, as `province/code`, e.g. `"MB/s0000193"`. Defaults to the closest one
+    /// to the coordinates.
+    pub site: Option<String>,
+    pub unit: Option<GenericWeatherUnit>,
+}
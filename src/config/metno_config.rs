@@ -0,0 +1,15 @@
+use super::GenericWeatherUnit;
+use serde_derive::Deserialize;
+
+/// MET Norway doesn't need a key, so this whole section is optional.
+#[derive(Debug, Deserialize, PartialEq)]
+#[cfg_attr(test, derive(Default))]
+#[serde(deny_unknown_fields)]
+pub struct MetNoConfig {
+    /// Who's asking, which MET Norway's terms ask for, e.g. `"nimbus-alt you@example.com"`.
+    /// Defaults to the app's name and version.
+    pub user_agent: Option<String>,
+    pub unit: Option<GenericWeatherUnit>,
+    /// Ask for the `complete` forecast, which adds probabilities, dew points and gusts.
+    pub complete: Option<bool>,
+}
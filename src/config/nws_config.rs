@@ -0,0 +1,12 @@
+use super::GenericWeatherUnit;
+use serde_derive::Deserialize;
+
+/// The NWS doesn't use keys, but wants a `User-Agent` that says who's asking, e.g.
+/// `"(nimbus-alt, you@example.com)"`.
+#[derive(Debug, Deserialize, PartialEq)]
+#[cfg_attr(test, derive(Default))]
+#[serde(deny_unknown_fields)]
+pub struct NwsConfig {
+    pub user_agent: String,
+    pub unit: Option<GenericWeatherUnit>,
+}
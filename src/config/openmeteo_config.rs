@@ -0,0 +1,10 @@
+use super::GenericWeatherUnit;
+use serde_derive::Deserialize;
+
+/// Open-Meteo doesn't need a key, so this whole section is optional.
+#[derive(Debug, Deserialize, PartialEq)]
+#[cfg_attr(test, derive(Default))]
+#[serde(deny_unknown_fields)]
+pub struct OpenMeteoConfig {
+    pub unit: Option<GenericWeatherUnit>,
+}
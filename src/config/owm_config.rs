@@ -3,11 +3,18 @@ use super::GenericWeatherUnit;
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, PartialEq)]
+#[cfg_attr(test, derive(Default))]
 #[serde(deny_unknown_fields)]
 pub struct OwmConfig {
     pub key: String,
     pub location_id: Option<String>,
     pub unit: Option<OwmUnit>,
+    /// Send requests here instead, e.g. to a caching proxy or a mock server. It replaces
+    /// `https://api.openweathermap.org`, and the paths under it stay the same.
+    pub base_url: Option<String>,
+    /// How many calls a day are allowed, One Call included. Once they're used up, cached
+    /// responses are served until midnight UTC.
+    pub daily_budget: Option<u64>,
 }
 
 #[derive(
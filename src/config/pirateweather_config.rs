@@ -0,0 +1,11 @@
+use super::DarkSkyUnit;
+use serde_derive::Deserialize;
+
+/// Pirate Weather takes the same units as DarkSky.
+#[derive(Debug, Deserialize, PartialEq)]
+#[cfg_attr(test, derive(Default))]
+#[serde(deny_unknown_fields)]
+pub struct PirateWeatherConfig {
+    pub key: String,
+    pub unit: Option<DarkSkyUnit>,
+}
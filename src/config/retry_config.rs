@@ -0,0 +1,43 @@
+use serde_derive::Deserialize;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// How requests that fail for a reason that might pass (a connection error, a timeout, a server
+/// error or a rate limit) are tried again. API errors like a bad key never are.
+#[derive(Debug, Deserialize, PartialEq)]
+#[cfg_attr(test, derive(Default))]
+#[serde(deny_unknown_fields)]
+pub struct RetryConfig {
+    /// How many times a request is tried, all together. `1` turns retrying off. Defaults to 3.
+    pub attempts: Option<u32>,
+    /// How long to wait before the first retry, in milliseconds. It doubles after each one.
+    /// Defaults to 500.
+    pub backoff: Option<u64>,
+    /// The longest wait before a retry, in milliseconds. An API that asks for a longer one with
+    /// `Retry-After` isn't tried again. Defaults to 10 seconds.
+    pub max_backoff: Option<u64>,
+}
+
+impl RetryConfig {
+    pub fn attempts(&self) -> u32 {
+        self.attempts.unwrap_or(3).max(1)
+    }
+
+    /// The wait before retry number `retry`, counting from 0. Up to half of it is random, so
+    /// requests that failed together aren't all tried again together.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .backoff
+            .unwrap_or(500)
+            .saturating_mul(2_u64.saturating_pow(retry))
+            .min(self.max_backoff().as_millis() as u64);
+        let jitter = RandomState::new().build_hasher().finish() % (backoff / 2 + 1);
+
+        Duration::from_millis(backoff - jitter)
+    }
+
+    pub fn max_backoff(&self) -> Duration {
+        Duration::from_millis(self.max_backoff.unwrap_or(10_000))
+    }
+}
@@ -0,0 +1,359 @@
+//! The `history` command: historical data for a range of days, saved as CSV.
+
+use crate::config::DarkSkyUnit;
+use crate::weather_api::darksky::{DataPoint, Forecast};
+use crate::weather_api::{Fetched, Fetcher, HistoricalApi};
+use chrono::{NaiveDate, TimeZone};
+use chrono_tz::Tz;
+use csv::{Reader, Writer, WriterBuilder};
+use failure::{Error, Fail};
+use serde_derive::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+#[derive(Fail, Debug)]
+#[fail(display = "the range starts ({}) after it ends ({})", _0, _1)]
+pub struct BackwardsRange(NaiveDate, NaiveDate);
+
+#[derive(Fail, Debug)]
+#[fail(
+    display = "{} doesn't look like a history CSV file (it has no date column)",
+    _0
+)]
+pub struct NotHistoryFile(String);
+
+#[derive(Fail, Debug)]
+#[fail(
+    display = "{} has no units column, since an older version wrote it; save to a new file",
+    _0
+)]
+pub struct NoUnitsColumn(String);
+
+/// Every day from `from` to `to` (inclusive), fetched `jobs` days at a time, and written to
+/// `output`.
+///
+/// Each row is a data point from the day's `hourly` or `daily` block, after a `date`, a `block`
+/// and a `units` column. `units` is the DarkSky unit system the row's numbers are in (`si`, `ca`,
+/// `uk2` or `us`), since it can change between runs; `[display]` doesn't apply here. A day
+/// without any data gets a single row with `none` as its block.
+///
+/// Days already in `output` are skipped, so an interrupted run can be resumed by running it
+/// again.
+#[derive(Debug)]
+pub struct History {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub output: PathBuf,
+    pub jobs: usize,
+    /// The unit system asked for, in case DarkSky doesn't say which one it answered in.
+    pub unit: Option<DarkSkyUnit>,
+}
+
+/// What a run of [`History`] did.
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    /// Days fetched and written.
+    pub written: usize,
+    /// Days that were already in the file.
+    pub skipped: usize,
+    /// Whether any day came from an expired cache entry.
+    pub stale: bool,
+}
+
+#[derive(Serialize)]
+struct Row<'a> {
+    date: &'a str,
+    block: &'a str,
+    units: DarkSkyUnit,
+}
+
+impl History {
+    pub fn run<'a, A>(&self, api: &A, fetcher: &Fetcher, tz: Tz) -> Result<Summary, Error>
+    where
+        A: HistoricalApi<'a, Historical = Forecast> + Sync,
+    {
+        if self.from > self.to {
+            return Err(BackwardsRange(self.from, self.to).into());
+        }
+
+        let done = done_days(&self.output)?;
+        let all: Vec<NaiveDate> = self
+            .from
+            .iter_days()
+            .take_while(|day| *day <= self.to)
+            .collect();
+        let days: Vec<NaiveDate> = all
+            .iter()
+            .filter(|day| !done.contains(day))
+            .cloned()
+            .collect();
+        let summary = Summary {
+            skipped: all.len() - days.len(),
+            ..Summary::default()
+        };
+        if days.is_empty() {
+            return Ok(summary);
+        }
+
+        let mut writer = writer(&self.output)?;
+        let next = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        let (sender, receiver) = mpsc::channel::<(usize, Result<Fetched<Forecast>, Error>)>();
+
+        let worker = |sender: mpsc::Sender<_>| loop {
+            if failed.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let i = next.fetch_add(1, Ordering::Relaxed);
+            let day = match days.get(i) {
+                Some(day) => *day,
+                None => break,
+            };
+
+            debug!("fetching history for {}", day);
+            let fetched = api.fetch_historical(fetcher, midnight(day, tz));
+            if sender.send((i, fetched)).is_err() {
+                break;
+            }
+        };
+
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.clamp(1, days.len()) {
+                let sender = sender.clone();
+                scope.spawn(|| worker(sender));
+            }
+            drop(sender);
+
+            // Days finish in any order, but they're written in order, so that a resumed run
+            // never leaves a gap.
+            let mut pending = BTreeMap::new();
+            let mut result = Ok(summary);
+            for (i, fetched) in receiver {
+                let summary = match (&mut result, fetched) {
+                    (Ok(summary), Ok(fetched)) => {
+                        pending.insert(i, fetched);
+                        summary
+                    }
+                    (Ok(_), Err(e)) => {
+                        failed.store(true, Ordering::Relaxed);
+                        result = Err(e);
+                        continue;
+                    }
+                    (Err(_), _) => continue,
+                };
+
+                while let Some(fetched) = pending.remove(&summary.written) {
+                    let day = days[summary.written];
+                    if let Err(e) = write_day(&mut writer, day, fetched.value, self.unit) {
+                        failed.store(true, Ordering::Relaxed);
+                        result = Err(e);
+                        break;
+                    }
+
+                    info!("wrote history for {}", day);
+                    summary.written += 1;
+                    summary.stale |= fetched.stale;
+                }
+            }
+
+            result
+        })
+    }
+}
+
+/// The days already written to `path`.
+fn done_days(path: &Path) -> Result<HashSet<NaiveDate>, Error> {
+    if !path.exists() {
+        return Ok(HashSet::new());
+    }
+
+    let mut reader = Reader::from_path(path)?;
+    if reader.headers()?.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let column = reader
+        .headers()?
+        .iter()
+        .position(|header| header == "date")
+        .ok_or_else(|| NotHistoryFile(path.display().to_string()))?;
+    // New rows have one, and a CSV file's rows all need the same columns.
+    if !reader.headers()?.iter().any(|header| header == "units") {
+        return Err(NoUnitsColumn(path.display().to_string()).into());
+    }
+
+    let mut days = HashSet::new();
+    for record in reader.records() {
+        if let Some(day) = record?.get(column) {
+            days.insert(day.parse::<NaiveDate>()?);
+        }
+    }
+
+    Ok(days)
+}
+
+/// A writer that adds to the end of `path`, starting with a header if it's empty.
+fn writer(path: &Path) -> Result<Writer<File>, Error> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let empty = file.metadata()?.len() == 0;
+
+    Ok(WriterBuilder::new()
+        .has_headers(empty)
+        // Big enough to hold a whole day, so it's written all at once when flushed.
+        .buffer_capacity(1 << 20)
+        .from_writer(file))
+}
+
+fn write_day(
+    writer: &mut Writer<File>,
+    day: NaiveDate,
+    forecast: Forecast,
+    unit: Option<DarkSkyUnit>,
+) -> Result<(), Error> {
+    let date = day.to_string();
+    let units = forecast.unit(unit);
+    let (hourly, daily) = forecast.into_data_points();
+
+    if hourly.is_empty() && daily.is_empty() {
+        // Otherwise it'd be fetched again every time the run is resumed.
+        let row = Row {
+            date: &date,
+            block: "none",
+            units,
+        };
+        writer.serialize((row, DataPoint::default()))?;
+    }
+    for (block, points) in &[("hourly", hourly), ("daily", daily)] {
+        for point in points {
+            let row = Row {
+                date: &date,
+                block,
+                units,
+            };
+            writer.serialize((row, point as &DataPoint))?;
+        }
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// The start of `day` in `tz`, which is when DarkSky's daily data for it starts.
+fn midnight(day: NaiveDate, tz: Tz) -> i64 {
+    day.and_hms_opt(0, 0, 0)
+        .and_then(|time| tz.from_local_datetime(&time).earliest())
+        // Midnight was skipped by a DST change, so use noon instead.
+        .or_else(|| {
+            day.and_hms_opt(12, 0, 0)
+                .and_then(|time| tz.from_local_datetime(&time).earliest())
+        })
+        .map_or(0, |time| time.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    fn forecast(time: i64) -> Forecast {
+        serde_json::from_value(json!({
+            "latitude": 49.9,
+            "longitude": -97.14,
+            "timezone": "America/Winnipeg",
+            "hourly": {
+                "data": [
+                    { "time": time, "temperature": -3.5, "icon": "snow" },
+                    { "time": time + 3600, "temperature": -4.0 }
+                ]
+            },
+            "daily": {
+                "data": [{ "time": time, "temperatureHigh": -1.0, "precipType": "snow" }]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn it_writes_a_row_per_data_point_and_resumes() {
+        let path = std::env::temp_dir().join(format!("{}-history.csv", crate_name!()));
+        let _ = fs::remove_file(&path);
+        let day = NaiveDate::from_ymd_opt(2018, 11, 13).unwrap();
+
+        assert!(done_days(&path).unwrap().is_empty());
+        write_day(
+            &mut writer(&path).unwrap(),
+            day,
+            forecast(1_542_088_800),
+            Some(DarkSkyUnit::Si),
+        )
+        .unwrap();
+        write_day(
+            &mut writer(&path).unwrap(),
+            day.succ_opt().unwrap(),
+            forecast(1_542_175_200),
+            Some(DarkSkyUnit::Si),
+        )
+        .unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(7, lines.len());
+        assert!(lines[0].starts_with("date,block,units,apparent_temperature,"));
+        assert!(lines[0].contains(",temperature_high,"));
+        assert!(lines[1].starts_with("2018-11-13,hourly,si,"));
+        assert!(lines[1].contains(",snow,"));
+        assert!(lines[3].starts_with("2018-11-13,daily,si,"));
+        assert!(lines[6].starts_with("2018-11-14,daily,si,"));
+
+        let done = done_days(&path).unwrap();
+        assert_eq!(2, done.len());
+        assert!(done.contains(&day));
+    }
+
+    #[test]
+    fn it_marks_days_without_data_as_done() {
+        let path = std::env::temp_dir().join(format!("{}-empty-history.csv", crate_name!()));
+        let _ = fs::remove_file(&path);
+        let day = NaiveDate::from_ymd_opt(2018, 11, 13).unwrap();
+        let empty: Forecast = serde_json::from_value(json!({
+            "latitude": 49.9,
+            "longitude": -97.14,
+            "timezone": "America/Winnipeg",
+            "flags": { "sources": [], "units": "ca" }
+        }))
+        .unwrap();
+
+        write_day(&mut writer(&path).unwrap(), day, empty, None).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("2018-11-13,none,ca,"));
+        assert!(done_days(&path).unwrap().contains(&day));
+    }
+
+    #[test]
+    fn it_refuses_other_csv_files() {
+        let path = std::env::temp_dir().join(format!("{}-not-history.csv", crate_name!()));
+        fs::write(&path, "a,b\n1,2\n").unwrap();
+        assert!(done_days(&path).is_err());
+
+        // Written before there was a units column.
+        fs::write(&path, "date,block,temperature\n2018-11-13,hourly,-3.5\n").unwrap();
+        assert!(done_days(&path).is_err());
+    }
+
+    #[test]
+    fn it_starts_days_at_local_midnight() {
+        let day = NaiveDate::from_ymd_opt(2018, 11, 13).unwrap();
+        assert_eq!(1_542_088_800, midnight(day, chrono_tz::America::Winnipeg));
+    }
+}
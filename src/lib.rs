@@ -1,3 +1,6 @@
+// `#[derive(Fail)]` puts its impls inside a named const, which this lint flags on newer compilers.
+#![allow(non_local_definitions)]
+
 #[macro_use]
 extern crate log;
 
@@ -8,20 +11,59 @@ extern crate clap;
 extern crate strum_macros;
 
 pub mod app;
+mod cache;
 mod config;
+mod history;
+pub mod output;
+mod time;
+mod usage;
+pub mod weather;
 mod weather_api;
 
+use self::cache::Cache;
 pub use self::config::*;
+use self::history::History;
+use self::output::Options;
+use self::usage::{Ledger, UsageTable};
+use self::weather::Report;
 use self::weather_api::darksky::DarkSky;
-use self::weather_api::owm::Owm;
-pub use self::weather_api::{ForecastApi, HistoricalApi, WeatherApi};
+use self::weather_api::eccc::Eccc;
+use self::weather_api::join;
+use self::weather_api::metno::MetNo;
+use self::weather_api::nws::Nws;
+use self::weather_api::openmeteo::OpenMeteo;
+use self::weather_api::owm::{OneCall, Owm};
+use self::weather_api::pirateweather::PirateWeather;
+pub use self::weather_api::{Fetcher, ForecastApi, HistoricalApi, Provider, WeatherApi};
+use chrono_tz::Tz;
 use clap::ArgMatches;
 use env_logger::Builder;
-use failure::Error;
+use failure::{err_msg, Error};
 use log::LevelFilter;
 use reqwest::Client;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How fresh the printed weather is, which decides the exit code.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// Everything came straight from the providers, or from unexpired cache entries.
+    Fresh,
+    /// At least one provider couldn't be reached, so expired data from the cache was printed.
+    Stale,
+}
 
-pub fn run(config: &Config, matches: &ArgMatches) -> Result<(), Error> {
+impl Status {
+    /// `0` when fresh and `2` when stale, so scripts can tell them apart from errors (`1`).
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Status::Fresh => 0,
+            Status::Stale => 2,
+        }
+    }
+}
+
+pub fn run(config: &Config, matches: &ArgMatches) -> Result<Status, Error> {
     let arg_filter = if std::env::var("RUST_LOG").is_ok() || matches.occurrences_of("verbose") == 0
     {
         Builder::from_default_env().build().filter()
@@ -46,28 +88,368 @@ pub fn run(config: &Config, matches: &ArgMatches) -> Result<(), Error> {
     info!("logging enabled");
     debug!("{:?}", config);
 
-    let owm = Owm::new(&config, &matches);
-    info!("{:?}", owm);
-    info!("owm url: {}", owm.url());
-
-    let darksky = DarkSky::new(&config, &matches);
-    debug!("{:?}", darksky);
-    debug!("darksky url: {}", darksky.url());
+    let ledger = Ledger::new(config);
+    if let ("usage", Some(_)) = matches.subcommand() {
+        let usage = ledger.as_ref().map(Ledger::usage).unwrap_or_default();
+        print!("{}", UsageTable(&usage));
+        return Ok(Status::Fresh);
+    }
 
     // TODO: maybe remove this flag once testing is setup
     if !matches.is_present("live") {
-        return Ok(());
+        return Ok(Status::Fresh);
     }
 
-    let client = Client::new();
+    let timeout = Duration::from_secs(if matches.is_present("timeout") {
+        value_t!(matches, "timeout", u64)?
+    } else {
+        config.timeout.unwrap_or(30)
+    });
+    let client = Client::builder().timeout(timeout).build()?;
+    static DEFAULT_RETRY: RetryConfig = RetryConfig {
+        attempts: None,
+        backoff: None,
+        max_backoff: None,
+    };
+    let mut fetcher = Fetcher::new(&client, Cache::new(config, matches))
+        .retry(config.retry.as_ref().unwrap_or(&DEFAULT_RETRY));
+    if let Some(ledger) = &ledger {
+        fetcher = fetcher.ledger(ledger);
+    }
 
-    darksky.current(&client).map(|forecast| {
-        println!("{:?}", forecast);
-    })?;
+    // Providers are only created once they're needed, since not all of them may be configured.
+    if let ("history", Some(m)) = matches.subcommand() {
+        let darksky = DarkSky::new(config, matches);
+        let tz = time_zone(config, &darksky, &fetcher)?;
+        let history = History {
+            from: time::parse_date(m.value_of("from").unwrap_or_default(), tz)?,
+            to: time::parse_date(m.value_of("to").unwrap_or_default(), tz)?,
+            output: m.value_of_os("output").unwrap_or_default().into(),
+            jobs: value_t!(m, "jobs", usize)?,
+            unit: darksky.unit,
+        };
+
+        let summary = history.run(&darksky, &fetcher, tz)?;
+        eprintln!(
+            "Wrote {} days to {} ({} already there)",
+            summary.written,
+            history.output.display(),
+            summary.skipped
+        );
+
+        return Ok(if summary.stale {
+            Status::Stale
+        } else {
+            Status::Fresh
+        });
+    }
+
+    // Nothing else starts fetching after the deadline. History can take a lot longer, so it only
+    // has each request's timeout.
+    let fetcher = fetcher.until(Instant::now() + timeout);
+
+    let reports = match matches.subcommand() {
+        ("darksky", Some(_)) => {
+            let darksky = DarkSky::new(config, matches);
+            vec![match matches.value_of("time") {
+                Some(time) => historical_report(&darksky, time, config, &fetcher)?,
+                None => darksky.current_report(&fetcher)?,
+            }]
+        }
+        ("owm", Some(m)) if m.is_present("one-call") => vec![report(
+            &OneCall::new(config, matches),
+            config,
+            matches,
+            &fetcher,
+        )?],
+        ("owm", Some(m)) => {
+            no_history(Provider::Owm, matches)?;
+            let owm = Owm::new(config, matches);
+            vec![if m.is_present("forecast") {
+                owm.fetch_forecast(&fetcher)?
+                    .into_report(|forecast| owm.forecast_report(forecast))
+            } else {
+                owm.current_report(&fetcher)?
+            }]
+        }
+        ("openmeteo", Some(_)) => vec![report(
+            &OpenMeteo::new(config, matches),
+            config,
+            matches,
+            &fetcher,
+        )?],
+        ("eccc", Some(m)) => {
+            no_history(Provider::Eccc, matches)?;
+            let eccc = Eccc::new(config, matches);
+            vec![if m.is_present("forecast") {
+                eccc.fetch_forecast(&fetcher)?
+                    .into_report(|forecast| eccc.forecast_report(forecast))
+            } else {
+                eccc.current_report(&fetcher)?
+            }]
+        }
+        ("metno", Some(m)) => {
+            no_history(Provider::MetNo, matches)?;
+            let metno = MetNo::new(config, matches);
+            vec![if m.is_present("forecast") {
+                metno
+                    .fetch_forecast(&fetcher)?
+                    .into_report(|forecast| metno.forecast_report(forecast))
+            } else {
+                metno.current_report(&fetcher)?
+            }]
+        }
+        ("nws", Some(m)) => {
+            no_history(Provider::Nws, matches)?;
+            let nws = Nws::new(config, matches);
+            vec![if m.is_present("forecast") {
+                nws.fetch_forecast(&fetcher)?
+                    .into_report(|forecast| nws.forecast_report(forecast))
+            } else {
+                nws.current_report(&fetcher)?
+            }]
+        }
+        ("pirateweather", Some(_)) => vec![report(
+            &PirateWeather::new(config, matches),
+            config,
+            matches,
+            &fetcher,
+        )?],
+        ("compare", Some(_)) => {
+            every_report(&config.configured_providers(), config, matches, &fetcher)?
+        }
+        // Without a provider, historical data comes from DarkSky.
+        _ if matches.is_present("time") => vec![historical_report(
+            &DarkSky::new(config, matches),
+            matches.value_of("time").unwrap_or_default(),
+            config,
+            &fetcher,
+        )?],
+        _ => match &config.providers {
+            Some(providers) => vec![first_report(providers, config, matches, &fetcher)?],
+            None => each_report(
+                &[Provider::DarkSky, Provider::Owm],
+                config,
+                matches,
+                &fetcher,
+            )
+            .into_iter()
+            .collect::<Result<_, _>>()?,
+        },
+    };
+
+    print!(
+        "{}",
+        output::render(&Options::new(config, matches)?, &reports)?
+    );
+
+    if reports.iter().any(|report| report.stale) {
+        Ok(Status::Stale)
+    } else {
+        Ok(Status::Fresh)
+    }
+}
+
+/// What a provider's subcommand asks for: historical data with `--time`, the forecast with
+/// `--forecast`, or else the current conditions.
+fn report<'a, A>(
+    api: &A,
+    config: &Config,
+    matches: &ArgMatches,
+    fetcher: &Fetcher,
+) -> Result<Report, Error>
+where
+    A: ForecastApi<'a> + HistoricalApi<'a>,
+{
+    let forecast = matches
+        .subcommand()
+        .1
+        .is_some_and(|m| m.is_present("forecast"));
+
+    if let Some(time) = matches.value_of("time") {
+        historical_report(api, time, config, fetcher)
+    } else if forecast {
+        Ok(api
+            .fetch_forecast(fetcher)?
+            .into_report(|forecast| api.forecast_report(forecast)))
+    } else {
+        api.current_report(fetcher)
+    }
+}
 
-    owm.current(&client).map(|forecast| {
-        println!("{:?}", forecast);
+/// The conditions at `time`, as given on the command line.
+fn historical_report<'a, A: HistoricalApi<'a>>(
+    api: &A,
+    time: &str,
+    config: &Config,
+    fetcher: &Fetcher,
+) -> Result<Report, Error> {
+    let time = time::parse(time, time_zone(config, api, fetcher)?)?;
+    Ok(api
+        .fetch_historical(fetcher, time)?
+        .into_report(|historical| api.historical_report(historical, time)))
+}
+
+/// `--time` is an error for the providers that have no historical data, rather than being ignored.
+fn no_history(provider: Provider, matches: &ArgMatches) -> Result<(), Error> {
+    if matches.is_present("time") {
+        Err(err_msg(format!(
+            "{} has no historical data for --time",
+            provider.name()
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// The current conditions from the first of the providers that answers. The ones that failed
+/// before it are listed in the report.
+///
+/// Old data from the cache is only used when none of them could give anything fresher.
+fn first_report(
+    providers: &[Provider],
+    config: &Config,
+    matches: &ArgMatches,
+    fetcher: &Fetcher,
+) -> Result<Report, Error> {
+    let mut failed = Vec::new();
+    let mut stale = None;
+
+    for &provider in providers {
+        match current_report(provider, config, matches, fetcher) {
+            Ok(report) if report.stale => {
+                warn!(
+                    "{} only had stale data, trying the next provider",
+                    provider.name()
+                );
+                stale = stale.or(Some(report));
+            }
+            Ok(mut report) => {
+                report.failed = failed;
+                return Ok(report);
+            }
+            Err(e) => {
+                warn!(
+                    "{} failed, trying the next provider: {}",
+                    provider.name(),
+                    e
+                );
+                failed.push(provider);
+            }
+        }
+    }
+
+    stale
+        .map(|mut report| {
+            report.failed = failed.clone();
+            report
+        })
+        .ok_or_else(|| {
+            let names: Vec<_> = failed.iter().map(|provider| provider.name()).collect();
+            err_msg(format!("every provider failed: {}", names.join(", ")))
+        })
+}
+
+/// The current conditions from each of the providers that answers.
+fn every_report(
+    providers: &[Provider],
+    config: &Config,
+    matches: &ArgMatches,
+    fetcher: &Fetcher,
+) -> Result<Vec<Report>, Error> {
+    let reports: Vec<_> = providers
+        .iter()
+        .zip(each_report(providers, config, matches, fetcher))
+        .filter_map(|(provider, report)| match report {
+            Ok(report) => Some(report),
+            Err(e) => {
+                warn!("{} failed: {}", provider.name(), e);
+                None
+            }
+        })
+        .collect();
+
+    if reports.is_empty() {
+        Err(err_msg("none of the providers answered"))
+    } else {
+        Ok(reports)
+    }
+}
+
+/// The current conditions from each provider, fetched at the same time.
+fn each_report(
+    providers: &[Provider],
+    config: &Config,
+    matches: &ArgMatches,
+    fetcher: &Fetcher,
+) -> Vec<Result<Report, Error>> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = providers
+            .iter()
+            .map(|&provider| {
+                scope.spawn(move || current_report(provider, config, matches, fetcher))
+            })
+            .collect();
+
+        handles.into_iter().map(join).collect()
+    })
+}
+
+/// The current conditions from any provider.
+fn current_report(
+    provider: Provider,
+    config: &Config,
+    matches: &ArgMatches,
+    fetcher: &Fetcher,
+) -> Result<Report, Error> {
+    match provider {
+        Provider::DarkSky => DarkSky::new(config, matches).current_report(fetcher),
+        Provider::Eccc => Eccc::new(config, matches).current_report(fetcher),
+        Provider::MetNo => MetNo::new(config, matches).current_report(fetcher),
+        Provider::Nws => Nws::new(config, matches).current_report(fetcher),
+        Provider::OpenMeteo => OpenMeteo::new(config, matches).current_report(fetcher),
+        Provider::Owm => Owm::new(config, matches).current_report(fetcher),
+        Provider::OwmOneCall => OneCall::new(config, matches).current_report(fetcher),
+        Provider::PirateWeather => PirateWeather::new(config, matches).current_report(fetcher),
+    }
+}
+
+/// The time zone times on the command line are in: the configured one, or else the one the
+/// provider gives for the location.
+fn time_zone<'a, A: WeatherApi<'a>>(
+    config: &Config,
+    api: &A,
+    fetcher: &Fetcher,
+) -> Result<Tz, Error> {
+    if let Some(tz) = config.timezone {
+        return Ok(tz);
+    }
+
+    let report = api.current_report(fetcher)?;
+    let name = report.location.timezone.as_ref().ok_or_else(|| {
+        err_msg("couldn't tell which time zone the location is in; set `timezone` in the config")
     })?;
 
-    Ok(())
+    name.parse::<Tz>()
+        .map_err(|e| err_msg(format!("unknown time zone {}: {}", name, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::build_cli;
+
+    #[test]
+    fn it_rejects_times_for_providers_without_history() {
+        let current = build_cli().get_matches_from(vec!["nimbus-alt", "owm"]);
+        let historical = build_cli().get_matches_from(vec!["nimbus-alt", "--time=-1d", "owm"]);
+
+        assert!(no_history(Provider::Owm, &current).is_ok());
+        assert_eq!(
+            "OpenWeatherMap has no historical data for --time",
+            no_history(Provider::Owm, &historical)
+                .unwrap_err()
+                .to_string()
+        );
+    }
 }
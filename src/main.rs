@@ -1,4 +1,3 @@
-use dotenv;
 use nimbus_alt::{self, app, Config};
 
 fn main() {
@@ -6,8 +5,11 @@ fn main() {
 
     let matches = app::build_cli().get_matches();
 
-    if let Err(e) = Config::from_file().and_then(|config| nimbus_alt::run(&config, &matches)) {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+    match Config::from_file().and_then(|config| nimbus_alt::run(&config, &matches)) {
+        Ok(status) => std::process::exit(status.exit_code()),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
     }
 }
@@ -0,0 +1,247 @@
+//! Rendering of [`Report`](crate::weather::Report)s.
+
+mod bar;
+mod comparison;
+mod forecast;
+mod json;
+mod template;
+mod text;
+
+pub use self::bar::Bar;
+pub use self::comparison::Comparison;
+pub use self::forecast::ForecastTable;
+pub use self::json::{Document, SCHEMA_VERSION};
+pub use self::template::{Template, TemplateError, PLACEHOLDERS};
+pub use self::text::CurrentConditions;
+use crate::config::{ArgEnum, Config, DisplayConfig, GenericWeatherUnit};
+use crate::weather::{
+    DistanceUnit, PrecipitationUnit, PressureUnit, Report, SpeedUnit, TemperatureUnit, Units,
+};
+use clap::ArgMatches;
+use failure::Error;
+
+#[derive(Debug, Default, Display, EnumString, EnumIter, Copy, Clone, PartialEq)]
+#[strum(serialize_all = "kebab_case")]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+    Ndjson,
+    Bar,
+}
+
+impl ArgEnum for Format {
+    const VARIANTS: &'static [&'static str] = &["text", "json", "ndjson", "bar"];
+}
+
+/// Everything that decides how reports are printed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Options {
+    pub format: Format,
+    pub bar: Bar,
+    /// Replaces the default text output.
+    pub template: Option<Template>,
+    /// Show the forecast instead of the current conditions.
+    pub forecast: bool,
+    /// Show the reports side by side, from `compare`.
+    pub compare: bool,
+    /// The unit to show each kind of measurement in, whatever the providers were asked for. The
+    /// kinds without one are shown in the unit the report came in.
+    pub units: DisplayConfig,
+}
+
+impl Options {
+    pub fn new(config: &Config, m: &ArgMatches) -> Result<Self, Error> {
+        // A template on the command line overrides the configured one.
+        let template = match m.value_of("template").or(config.template.as_deref()) {
+            Some(template) => Some(template.parse::<Template>()?),
+            None => None,
+        };
+
+        Ok(Self {
+            format: value_t!(m.value_of("format"), Format).unwrap_or_default(),
+            bar: value_t!(m.value_of("bar"), Bar).unwrap_or_default(),
+            template,
+            forecast: m
+                .subcommand()
+                .1
+                .is_some_and(|provider| provider.is_present("forecast")),
+            compare: m.subcommand_name() == Some("compare"),
+            units: display_units(config, m),
+        })
+    }
+
+    /// The units to show a report in. Kelvin is never picked on its own, since OWM only answers
+    /// in it when it isn't asked for a unit.
+    pub fn units(&self, report: &Report) -> Units {
+        let temperature = match report.units.temperature {
+            TemperatureUnit::Kelvin => TemperatureUnit::Celsius,
+            unit => unit,
+        };
+
+        Units {
+            temperature: self.units.temperature.unwrap_or(temperature),
+            speed: self.units.speed.unwrap_or(report.units.speed),
+            pressure: self.units.pressure.unwrap_or(report.units.pressure),
+            distance: self.units.distance.unwrap_or(report.units.distance),
+            precipitation: self
+                .units
+                .precipitation
+                .unwrap_or(report.units.precipitation),
+        }
+    }
+}
+
+/// The unit for each kind of measurement, picked in the order [`DisplayConfig`] describes.
+fn display_units(config: &Config, m: &ArgMatches) -> DisplayConfig {
+    let flag = value_t!(m.value_of("units"), GenericWeatherUnit)
+        .ok()
+        .map(Units::from);
+    let display = config.display.unwrap_or_default();
+    let configured = config.unit.map(Units::from);
+
+    DisplayConfig {
+        temperature: value_t!(m.value_of("temperature-unit"), TemperatureUnit)
+            .ok()
+            .or(flag.map(|units| units.temperature))
+            .or(display.temperature)
+            .or(configured.map(|units| units.temperature)),
+        speed: value_t!(m.value_of("speed-unit"), SpeedUnit)
+            .ok()
+            .or(flag.map(|units| units.speed))
+            .or(display.speed)
+            .or(configured.map(|units| units.speed)),
+        pressure: value_t!(m.value_of("pressure-unit"), PressureUnit)
+            .ok()
+            .or(flag.map(|units| units.pressure))
+            .or(display.pressure)
+            .or(configured.map(|units| units.pressure)),
+        distance: value_t!(m.value_of("distance-unit"), DistanceUnit)
+            .ok()
+            .or(flag.map(|units| units.distance))
+            .or(display.distance)
+            .or(configured.map(|units| units.distance)),
+        precipitation: value_t!(m.value_of("precipitation-unit"), PrecipitationUnit)
+            .ok()
+            .or(flag.map(|units| units.precipitation))
+            .or(display.precipitation)
+            .or(configured.map(|units| units.precipitation)),
+    }
+}
+
+/// Render the reports, ready to be printed as-is.
+pub fn render(options: &Options, reports: &[Report]) -> Result<String, Error> {
+    let reports: Vec<_> = reports
+        .iter()
+        .map(|report| report.clone().to(options.units(report)))
+        .collect();
+    let reports = &reports[..];
+
+    match (options.format, &options.template) {
+        (Format::Text, _) if options.compare => Ok(Comparison(reports).to_string()),
+        (Format::Text, _) if options.forecast => Ok(reports
+            .iter()
+            .map(|report| ForecastTable(report).to_string())
+            .collect::<Vec<_>>()
+            .join("\n")),
+        (Format::Text, Some(template)) => Ok(reports
+            .iter()
+            .map(|report| template.render(report) + "\n")
+            .collect()),
+        (Format::Text, None) => Ok(reports
+            .iter()
+            .map(|report| CurrentConditions(report).to_string())
+            .collect::<Vec<_>>()
+            .join("\n")),
+        (Format::Json, _) => json::json(reports),
+        (Format::Ndjson, _) => json::ndjson(reports),
+        (Format::Bar, _) => bar::bar(options.bar, reports),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::build_cli;
+    use crate::config::DarkSkyUnit;
+    use crate::weather::{Observation, Pressure, Speed, Temperature};
+    use crate::weather_api::Provider;
+
+    #[test]
+    fn it_renders_in_the_units_asked_for() {
+        // OWM answers in Kelvin when it isn't asked for a unit.
+        let mut report = Report::new(Provider::Owm, Units::from(None));
+        let mut current = Observation::new(0);
+        current.temperature = Some(Temperature::new(269.65, TemperatureUnit::Kelvin));
+        report.current = Some(current);
+        let reports = [report];
+
+        let options = Options {
+            template: Some("{temp:.1}{unit}".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!("-3.5°C\n", render(&options, &reports).unwrap());
+
+        let options = Options {
+            units: DisplayConfig {
+                temperature: Some(TemperatureUnit::Fahrenheit),
+                ..Default::default()
+            },
+            ..options
+        };
+        assert_eq!("25.7°F\n", render(&options, &reports).unwrap());
+    }
+
+    #[test]
+    fn it_picks_a_display_unit_for_each_kind_of_measurement() {
+        let config = Config {
+            unit: Some(GenericWeatherUnit::Imperial),
+            display: Some(DisplayConfig {
+                temperature: Some(TemperatureUnit::Celsius),
+                speed: Some(SpeedUnit::MetresPerSecond),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        // `--units` wins over `[display]`, and metric wind is in m/s.
+        let m = build_cli().get_matches_from(vec!["nimbus-alt", "--units=metric"]);
+        let options = Options::new(&config, &m).unwrap();
+        assert_eq!(Some(SpeedUnit::MetresPerSecond), options.units.speed);
+        assert_eq!(Some(DistanceUnit::Kilometres), options.units.distance);
+
+        let m =
+            build_cli().get_matches_from(vec!["nimbus-alt", "--speed-unit=kilometres_per_hour"]);
+        let options = Options::new(&config, &m).unwrap();
+        assert_eq!(
+            DisplayConfig {
+                temperature: Some(TemperatureUnit::Celsius),
+                speed: Some(SpeedUnit::KilometresPerHour),
+                pressure: Some(PressureUnit::Hectopascals),
+                distance: Some(DistanceUnit::Miles),
+                precipitation: Some(PrecipitationUnit::InchesPerHour),
+            },
+            options.units
+        );
+
+        // DarkSky's `us` answers in Fahrenheit, mph and hPa.
+        let mut report = Report::new(Provider::DarkSky, Units::from(DarkSkyUnit::Us));
+        let mut current = Observation::new(0);
+        current.temperature = Some(Temperature::new(25.7, TemperatureUnit::Fahrenheit));
+        current.wind_speed = Some(Speed::new(10., SpeedUnit::MilesPerHour));
+        current.pressure = Some(Pressure::new(1021.3, PressureUnit::Hectopascals));
+        report.current = Some(current);
+
+        let options = Options {
+            template: Some(
+                "{temp:.1}{unit} {wind:.1} {wind_unit} {pressure:.1} {pressure_unit}"
+                    .parse()
+                    .unwrap(),
+            ),
+            ..options
+        };
+        assert_eq!(
+            "-3.5°C 16.1 km/h 1021.3 hPa\n",
+            render(&options, &[report]).unwrap()
+        );
+    }
+}
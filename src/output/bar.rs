@@ -0,0 +1,157 @@
+use super::CurrentConditions;
+use crate::config::ArgEnum;
+use crate::weather::{Condition, Report, Unit};
+use failure::Error;
+use serde_derive::Serialize;
+
+/// The status bar to format the output for.
+#[derive(Debug, Default, Display, EnumString, EnumIter, Copy, Clone, PartialEq)]
+#[strum(serialize_all = "kebab_case")]
+pub enum Bar {
+    /// A single JSON object with `text`, `tooltip`, `class` and `percentage`, for a custom module
+    /// with `"return-type": "json"`.
+    #[default]
+    Waybar,
+    /// The full text on the first line and the short text on the second, for a blocklet with the
+    /// default `format`.
+    I3blocks,
+    /// A single line, for a `custom/script` module.
+    Polybar,
+}
+
+impl ArgEnum for Bar {
+    const VARIANTS: &'static [&'static str] = &["waybar", "i3blocks", "polybar"];
+}
+
+#[derive(Debug, Serialize)]
+struct Waybar {
+    text: String,
+    tooltip: String,
+    /// The [`Condition`], e.g. `partly-cloudy-night`, so the bar's CSS can style each one. `stale`
+    /// is added when the provider couldn't be reached and old data was used.
+    class: Vec<String>,
+    /// Chance of precipitation, or cloud cover when that isn't known, from 0 to 100.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    percentage: Option<u8>,
+}
+
+/// Render the first report for a status bar. Bars only have room for one location.
+pub fn bar(bar: Bar, reports: &[Report]) -> Result<String, Error> {
+    let report = match reports.first() {
+        Some(report) => report,
+        None => return Ok(String::new()),
+    };
+
+    match bar {
+        Bar::Waybar => {
+            let current = report.current.as_ref();
+            let mut class = vec![current
+                .map_or(Condition::Unknown, |current| current.condition)
+                .to_string()];
+            if report.stale {
+                class.push(String::from("stale"));
+            }
+
+            let waybar = Waybar {
+                text: text(report),
+                tooltip: escape_markup(CurrentConditions(report).to_string().trim_end()),
+                class,
+                percentage: current
+                    .and_then(|current| current.precip_probability.or(current.cloud_cover))
+                    .map(|ratio| (ratio * 100.).round().clamp(0., 100.) as u8),
+            };
+
+            Ok(serde_json::to_string(&waybar)? + "\n")
+        }
+        Bar::I3blocks => Ok(format!("{}\n{}\n", text(report), short_text(report))),
+        Bar::Polybar => Ok(format!("{}\n", text(report))),
+    }
+}
+
+/// The condition's icon, followed by the temperature, e.g. `☁ -3°C`. Stale data is marked with
+/// an asterisk, e.g. `☁ -3°C*`.
+fn text(report: &Report) -> String {
+    let text = match &report.current {
+        Some(current) => match current.temperature {
+            Some(_) => format!("{} {}", current.condition.icon(), short_text(report)),
+            None => current.condition.icon().to_string(),
+        },
+        None => String::from("?"),
+    };
+
+    if report.stale {
+        text + "*"
+    } else {
+        text
+    }
+}
+
+fn short_text(report: &Report) -> String {
+    report
+        .current
+        .as_ref()
+        .and_then(|current| current.temperature)
+        .map_or_else(
+            || String::from("?"),
+            |temperature| {
+                let unit = report.units.temperature;
+                format!("{:.0}{}", temperature.value_in(unit), unit.symbol())
+            },
+        )
+}
+
+/// Waybar treats tooltips as Pango markup.
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DarkSkyUnit;
+    use crate::weather::{Observation, Temperature, TemperatureUnit};
+    use crate::weather_api::Provider;
+
+    fn report() -> Report {
+        let mut report = Report::new(Provider::DarkSky, DarkSkyUnit::Si.into());
+        let mut current = Observation::new(0);
+        current.condition = Condition::PartlyCloudyNight;
+        current.temperature = Some(Temperature::new(-3.4, TemperatureUnit::Celsius));
+        current.precip_probability = Some(0.3);
+        report.current = Some(current);
+
+        report
+    }
+
+    #[test]
+    fn it_renders_for_waybar() {
+        let output: serde_json::Value =
+            serde_json::from_str(&bar(Bar::Waybar, &[report()]).unwrap()).unwrap();
+
+        assert_eq!("⛅ -3°C", output["text"]);
+        assert_eq!(serde_json::json!(["partly-cloudy-night"]), output["class"]);
+        assert_eq!(30, output["percentage"]);
+    }
+
+    #[test]
+    fn it_marks_stale_data() {
+        let mut report = report();
+        report.stale = true;
+        let output: serde_json::Value =
+            serde_json::from_str(&bar(Bar::Waybar, &[report]).unwrap()).unwrap();
+
+        assert_eq!("⛅ -3°C*", output["text"]);
+        assert_eq!(
+            serde_json::json!(["partly-cloudy-night", "stale"]),
+            output["class"]
+        );
+    }
+
+    #[test]
+    fn it_renders_for_i3blocks_and_polybar() {
+        assert_eq!("⛅ -3°C\n-3°C\n", bar(Bar::I3blocks, &[report()]).unwrap());
+        assert_eq!("⛅ -3°C\n", bar(Bar::Polybar, &[report()]).unwrap());
+    }
+}
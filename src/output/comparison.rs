@@ -0,0 +1,273 @@
+use super::text::place;
+use crate::weather::{Report, SpeedUnit, TemperatureUnit, Unit, Units};
+use std::fmt::{self, Write};
+
+/// Current conditions from several providers side by side, with what they agree on.
+///
+/// Everything is in the first report's units. `!` marks a spread wide enough that the providers
+/// disagree.
+///
+/// ```text
+/// Winnipeg, CA
+///                                Temp         Wind   Humidity   Precip
+///   Dark Sky                   -3.5°C      4.2 m/s        74%      20%
+///   OpenWeatherMap             -4.1°C      4.1 m/s        80%
+///   Open-Meteo                 -7.0°C      4.0 m/s        78%      60%
+///   Median                     -4.1°C      4.1 m/s        78%      40%
+///   Mean                       -4.9°C      4.1 m/s        77%      40%
+///   Spread                      3.5°C !    0.2 m/s         6%      40% !
+/// ```
+pub struct Comparison<'a>(pub &'a [Report]);
+
+impl<'a> fmt::Display for Comparison<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&table(self.0))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Column {
+    Temperature,
+    Wind,
+    Humidity,
+    Precipitation,
+}
+
+const COLUMNS: [Column; 4] = [
+    Column::Temperature,
+    Column::Wind,
+    Column::Humidity,
+    Column::Precipitation,
+];
+
+impl Column {
+    fn label(self) -> &'static str {
+        match self {
+            Column::Temperature => "Temp",
+            Column::Wind => "Wind",
+            Column::Humidity => "Humidity",
+            Column::Precipitation => "Precip",
+        }
+    }
+
+    fn width(self) -> usize {
+        match self {
+            Column::Temperature => 8,
+            Column::Wind => 10,
+            Column::Humidity => 8,
+            Column::Precipitation => 6,
+        }
+    }
+
+    /// The report's value, in `units`, with ratios as percentages.
+    fn value(self, report: &Report, units: &Units) -> Option<f64> {
+        let current = report.current.as_ref()?;
+        match self {
+            Column::Temperature => current
+                .temperature
+                .map(|temperature| temperature.value_in(units.temperature)),
+            Column::Wind => current.wind_speed.map(|speed| speed.value_in(units.speed)),
+            Column::Humidity => current.humidity.map(|humidity| humidity * 100.),
+            Column::Precipitation => current.precip_probability.map(|chance| chance * 100.),
+        }
+    }
+
+    fn format(self, value: f64, units: &Units) -> String {
+        match self {
+            Column::Temperature => format!("{:.1}{}", value, units.temperature.symbol()),
+            Column::Wind => format!("{:.1} {}", value, units.speed.symbol()),
+            Column::Humidity | Column::Precipitation => format!("{:.0}%", value),
+        }
+    }
+
+    /// Whether a spread this wide means the providers disagree.
+    fn disagrees(self, spread: f64, units: &Units) -> bool {
+        let threshold = match self {
+            // 2°C, as a difference rather than a temperature.
+            Column::Temperature => {
+                TemperatureUnit::Celsius.convert(2., units.temperature)
+                    - TemperatureUnit::Celsius.convert(0., units.temperature)
+            }
+            Column::Wind => SpeedUnit::MetresPerSecond.convert(3., units.speed),
+            Column::Humidity => 15.,
+            Column::Precipitation => 30.,
+        };
+
+        spread > threshold
+    }
+}
+
+fn table(reports: &[Report]) -> String {
+    let first = match reports.first() {
+        Some(first) => first,
+        None => return String::from("No providers answered\n"),
+    };
+    let units = first.units;
+
+    let mut output = place(first) + "\n";
+    let labels: Vec<_> = COLUMNS
+        .iter()
+        .map(|column| (column.label().to_string(), false))
+        .collect();
+    output += &row("", &labels);
+
+    let values: Vec<Vec<Option<f64>>> = reports
+        .iter()
+        .map(|report| {
+            COLUMNS
+                .iter()
+                .map(|column| column.value(report, &units))
+                .collect()
+        })
+        .collect();
+
+    for (report, values) in reports.iter().zip(&values) {
+        let name = if report.stale {
+            format!("{} (stale)", report.provider.name())
+        } else {
+            report.provider.name().to_string()
+        };
+        let cells: Vec<_> = COLUMNS
+            .iter()
+            .zip(values)
+            .map(|(column, value)| {
+                let cell = value.map(|value| column.format(value, &units));
+                (cell.unwrap_or_default(), false)
+            })
+            .collect();
+        output += &row(&name, &cells);
+    }
+
+    if reports.len() < 2 {
+        return output;
+    }
+
+    // Each column's values, leaving out the providers that didn't give one.
+    let columns: Vec<Vec<f64>> = (0..COLUMNS.len())
+        .map(|i| values.iter().filter_map(|values| values[i]).collect())
+        .collect();
+
+    output += &statistic("Median", &columns, &units, median, false);
+    output += &statistic("Mean", &columns, &units, mean, false);
+    output += &statistic("Spread", &columns, &units, spread, true);
+
+    output
+}
+
+/// A row with a statistic for each column, marking the ones the providers disagree on when
+/// `mark` is set.
+fn statistic(
+    name: &str,
+    columns: &[Vec<f64>],
+    units: &Units,
+    statistic: fn(&[f64]) -> Option<f64>,
+    mark: bool,
+) -> String {
+    let cells: Vec<_> = COLUMNS
+        .iter()
+        .zip(columns)
+        .map(|(column, values)| match statistic(values) {
+            Some(value) => (
+                column.format(value, units),
+                mark && column.disagrees(value, units),
+            ),
+            None => (String::new(), false),
+        })
+        .collect();
+
+    row(name, &cells)
+}
+
+/// A line of the table, where each cell can be marked with `!`.
+fn row(name: &str, cells: &[(String, bool)]) -> String {
+    let mut line = format!("  {:<24}", name);
+    for (column, (cell, marked)) in COLUMNS.iter().zip(cells) {
+        let mark = if *marked { " !" } else { "  " };
+        let _ = write!(line, " {:>width$}{}", cell, mark, width = column.width());
+    }
+
+    line.trim_end().to_string() + "\n"
+}
+
+fn median(values: &[f64]) -> Option<f64> {
+    let mut values = values.to_vec();
+    values.sort_by(f64::total_cmp);
+
+    let middle = values.len() / 2;
+    match values.len() {
+        0 => None,
+        len if len % 2 == 0 => Some((values[middle - 1] + values[middle]) / 2.),
+        _ => Some(values[middle]),
+    }
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+/// How far apart the highest and lowest values are.
+fn spread(values: &[f64]) -> Option<f64> {
+    let max = values.iter().copied().reduce(f64::max)?;
+    let min = values.iter().copied().reduce(f64::min)?;
+
+    Some(max - min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DarkSkyUnit, OwmUnit};
+    use crate::weather::Observation;
+    use crate::weather_api::Provider;
+
+    fn report(provider: Provider, units: Units, temperature: f64, humidity: f64) -> Report {
+        let mut report = Report::new(provider, units);
+        report.location.name = Some(String::from("Winnipeg"));
+
+        let mut current = Observation::new(0);
+        current.temperature = units.temperature.of(Some(temperature));
+        current.wind_speed = units.speed.of(Some(4.));
+        current.humidity = Some(humidity);
+        report.current = Some(current);
+
+        report
+    }
+
+    #[test]
+    fn it_finds_the_median_and_spread() {
+        assert_eq!(Some(2.), median(&[3., 1., 2.]));
+        assert_eq!(Some(2.5), median(&[4., 1., 2., 3.]));
+        assert_eq!(None, median(&[]));
+        assert_eq!(Some(2.), mean(&[1., 3.]));
+        assert_eq!(Some(3.), spread(&[2., -1., 1.5]));
+    }
+
+    #[test]
+    fn it_compares_providers_in_the_same_units() {
+        let reports = [
+            report(Provider::DarkSky, Units::from(DarkSkyUnit::Si), -3.5, 0.74),
+            // 20°F and 4 mph.
+            report(
+                Provider::Owm,
+                Units::from(Some(OwmUnit::Imperial)),
+                20.,
+                0.8,
+            ),
+        ];
+
+        assert_eq!(
+            "Winnipeg\n                               \
+             Temp         Wind   Humidity   Precip\n  \
+             Dark Sky                   -3.5°C      4.0 m/s        74%\n  \
+             OpenWeatherMap             -6.7°C      1.8 m/s        80%\n  \
+             Median                     -5.1°C      2.9 m/s        77%\n  \
+             Mean                       -5.1°C      2.9 m/s        77%\n  \
+             Spread                      3.2°C !    2.2 m/s         6%\n",
+            Comparison(&reports).to_string()
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use super::text::{compass, heading};
+use crate::weather::{HourlyPoint, Report, Unit};
+use chrono::{Local, TimeZone};
+use std::fmt::{self, Write};
+
+/// A forecast as a table, with a section for each day.
+///
+/// ```text
+/// Winnipeg, CA (OpenWeatherMap)
+///
+/// Tue Nov 13
+///   15:00  ☁    -3.5°C    4.1 m/s NW  20%  overcast clouds
+///   18:00  ❄    -5.0°C    3.0 m/s N   80%  light snow
+/// ```
+pub struct ForecastTable<'a>(pub &'a Report);
+
+impl<'a> fmt::Display for ForecastTable<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Hours are grouped into the location's days, not this machine's.
+        let location = &self.0.location;
+        match (location.time_zone(), location.utc_offset) {
+            (Some(tz), _) => f.write_str(&table(self.0, &tz)),
+            (None, Some(offset)) => f.write_str(&table(self.0, &offset)),
+            (None, None) => f.write_str(&table(self.0, &Local)),
+        }
+    }
+}
+
+fn table<Tz: TimeZone>(report: &Report, tz: &Tz) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    let mut output = heading(report) + "\n";
+    if report.hourly.is_empty() {
+        output += "No forecast available\n";
+        return output;
+    }
+
+    let mut day = None;
+    for point in &report.hourly {
+        let time = match tz.timestamp_opt(point.time, 0).single() {
+            Some(time) => time,
+            None => continue,
+        };
+
+        if day != Some(time.date_naive()) {
+            day = Some(time.date_naive());
+            let _ = write!(output, "\n{}\n", time.format("%a %b %e"));
+        }
+
+        let _ = writeln!(
+            output,
+            "  {}  {}  {}",
+            time.format("%H:%M"),
+            point.condition.icon(),
+            row(report, point).trim_end()
+        );
+    }
+
+    output
+}
+
+fn row(report: &Report, point: &HourlyPoint) -> String {
+    let units = &report.units;
+
+    let temperature = point.temperature.map_or_else(String::new, |temperature| {
+        let unit = units.temperature;
+        format!("{:.1}{}", temperature.value_in(unit), unit.symbol())
+    });
+    let wind = point.wind_speed.map_or_else(String::new, |speed| {
+        let direction = point.wind_bearing.map(compass).unwrap_or_default();
+        let unit = units.speed;
+        format!(
+            "{:.1} {} {}",
+            speed.value_in(unit),
+            unit.symbol(),
+            direction
+        )
+    });
+    let precipitation = point
+        .precip_probability
+        .map_or_else(String::new, |chance| format!("{:.0}%", chance * 100.));
+    let summary = point
+        .summary
+        .clone()
+        .unwrap_or_else(|| point.condition.to_string());
+
+    format!(
+        "{:>9}  {:<14} {:>4}  {}",
+        temperature, wind, precipitation, summary
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OwmUnit;
+    use crate::weather::{Condition, Speed, SpeedUnit, Temperature, TemperatureUnit};
+    use crate::weather_api::Provider;
+    use chrono::Utc;
+
+    #[test]
+    fn it_groups_the_forecast_by_day() {
+        let mut report = Report::new(Provider::Owm, Some(OwmUnit::Metric).into());
+        report.location.name = Some(String::from("Winnipeg"));
+
+        let mut first = HourlyPoint::new(1_542_121_200);
+        first.condition = Condition::Cloudy;
+        first.summary = Some(String::from("overcast clouds"));
+        first.temperature = Some(Temperature::new(-3.46, TemperatureUnit::Celsius));
+        first.wind_speed = Some(Speed::new(4.1, SpeedUnit::MetresPerSecond));
+        first.wind_bearing = Some(310.);
+        first.precip_probability = Some(0.2);
+
+        let mut second = HourlyPoint::new(1_542_121_200 + 3 * 3600);
+        second.condition = Condition::Snow;
+        second.temperature = Some(Temperature::new(-5., TemperatureUnit::Celsius));
+
+        let mut third = HourlyPoint::new(1_542_121_200 + 12 * 3600);
+        third.temperature = Some(Temperature::new(-10., TemperatureUnit::Celsius));
+
+        report.hourly = vec![first, second, third];
+
+        assert_eq!(
+            "Winnipeg (OpenWeatherMap)\n\
+             \n\
+             Tue Nov 13\n  \
+             15:00  ☁     -3.5°C  4.1 m/s NW      20%  overcast clouds\n  \
+             18:00  ❄     -5.0°C                       snow\n\
+             \n\
+             Wed Nov 14\n  \
+             03:00  ?    -10.0°C                       unknown\n",
+            table(&report, &Utc)
+        );
+    }
+
+    #[test]
+    fn it_uses_the_locations_days() {
+        let mut report = Report::new(Provider::Owm, Some(OwmUnit::Metric).into());
+        report.location.timezone = Some(String::from("America/Winnipeg"));
+        report.hourly = vec![
+            HourlyPoint::new(1_542_121_200),
+            HourlyPoint::new(1_542_121_200 + 12 * 3600),
+        ];
+
+        assert_eq!(
+            "Unknown location (OpenWeatherMap)\n\
+             \n\
+             Tue Nov 13\n  \
+             09:00  ?                                  unknown\n  \
+             21:00  ?                                  unknown\n",
+            ForecastTable(&report).to_string()
+        );
+    }
+}
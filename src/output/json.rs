@@ -0,0 +1,478 @@
+use crate::weather::{
+    self, Condition, DailySummary, DistanceUnit, HourlyPoint, Observation, PrecipitationUnit,
+    PressureUnit, Quantity, Report, Severity, SpeedUnit, TemperatureUnit,
+};
+use crate::weather_api::Provider;
+use failure::Error;
+use serde_derive::Serialize;
+
+/// Bumped whenever a field is renamed, removed or changes meaning. Adding fields doesn't count.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A report, as it's written by `--format json` and `--format ndjson`.
+///
+/// It's filled in field by field, so that changes to [`Report`] don't change the output unless
+/// they're carried over here too.
+///
+/// `json` prints a pretty-printed array of these documents, while `ndjson` prints one compact
+/// document per line. Each document looks like this (`null` marks values the provider didn't
+/// give):
+///
+/// ```json
+/// {
+///   "schema_version": 1,
+///   "provider": "owm",
+///   "location": {
+///     "name": "Winnipeg",
+///     "country": "CA",
+///     "latitude": 49.9,
+///     "longitude": -97.14,
+///     "timezone": null
+///   },
+///   "units": {
+///     "temperature": "celsius",
+///     "speed": "metres_per_second",
+///     "pressure": "hectopascals",
+///     "distance": "kilometres",
+///     "precipitation": "millimetres_per_hour"
+///   },
+///   "current": {
+///     "time": 1542143061,
+///     "summary": "clear sky",
+///     "condition": "clear-night",
+///     "temperature": -3.5,
+///     "apparent_temperature": null,
+///     "dew_point": null,
+///     "humidity": 0.74,
+///     "pressure": 1021.0,
+///     "wind_speed": 4.1,
+///     "wind_gust": null,
+///     "wind_bearing": 310.0,
+///     "cloud_cover": 0.01,
+///     "visibility": 24.14,
+///     "uv_index": null,
+///     "precip_intensity": null,
+///     "precip_probability": null,
+///     "sunrise": 1542116053,
+///     "sunset": 1542150213
+///   },
+///   "hourly": [],
+///   "daily": [],
+///   "alerts": [],
+///   "age": null,
+///   "stale": false,
+///   "failed": []
+/// }
+/// ```
+///
+/// * Measurements are in `units`: the ones picked with `--units`, `--temperature-unit` and the
+///   like (or `unit` and `[display]` in the config), or else the ones the provider answered in.
+/// * Times are Unix timestamps in seconds.
+/// * `humidity`, `cloud_cover` and `precip_probability` are fractions between 0 and 1.
+/// * `wind_bearing` is in degrees, and is the direction the wind is coming from.
+/// * `condition` is one of `clear-day`, `clear-night`, `partly-cloudy-day`,
+///   `partly-cloudy-night`, `cloudy`, `rain`, `snow`, `sleet`, `wind`, `fog`, `thunderstorm` or
+///   `unknown`.
+/// * `hourly` entries have the same fields as `current` except for `dew_point`, `wind_gust`,
+///   `visibility`, `uv_index`, `sunrise` and `sunset`.
+/// * `daily` entries have `time`, `summary`, `condition`, `temperature_high`, `temperature_low`,
+///   `apparent_temperature_high`, `apparent_temperature_low`, `humidity`, `pressure`,
+///   `wind_speed`, `wind_bearing`, `precip_intensity_max`, `precip_probability`, `uv_index`,
+///   `sunrise` and `sunset`.
+/// * `alerts` entries have `title`, `description`, `severity` (`advisory`, `watch`, `warning` or
+///   `null`), `regions`, `issued`, `expires` and `uri`.
+/// * `age` is how many seconds old the data is when it came from the cache, or `null` when it
+///   was just fetched.
+/// * `stale` is `true` when the provider couldn't be reached and expired data from the cache was
+///   used instead.
+/// * `failed` lists the providers that were tried first, but failed, when `providers` is
+///   configured.
+#[derive(Debug, Serialize)]
+pub struct Document<'a> {
+    schema_version: u32,
+    provider: Provider,
+    location: Location<'a>,
+    units: Units,
+    current: Option<Current<'a>>,
+    hourly: Vec<Hourly<'a>>,
+    daily: Vec<Daily<'a>>,
+    alerts: Vec<Alert<'a>>,
+    age: Option<u64>,
+    stale: bool,
+    failed: &'a [Provider],
+}
+
+#[derive(Debug, Serialize)]
+struct Location<'a> {
+    name: Option<&'a str>,
+    country: Option<&'a str>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    timezone: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct Units {
+    temperature: TemperatureUnit,
+    speed: SpeedUnit,
+    pressure: PressureUnit,
+    distance: DistanceUnit,
+    precipitation: PrecipitationUnit,
+}
+
+#[derive(Debug, Serialize)]
+struct Current<'a> {
+    time: i64,
+    summary: Option<&'a str>,
+    condition: Condition,
+    temperature: Option<f64>,
+    apparent_temperature: Option<f64>,
+    dew_point: Option<f64>,
+    humidity: Option<f64>,
+    pressure: Option<f64>,
+    wind_speed: Option<f64>,
+    wind_gust: Option<f64>,
+    wind_bearing: Option<f64>,
+    cloud_cover: Option<f64>,
+    visibility: Option<f64>,
+    uv_index: Option<f64>,
+    precip_intensity: Option<f64>,
+    precip_probability: Option<f64>,
+    sunrise: Option<i64>,
+    sunset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Hourly<'a> {
+    time: i64,
+    summary: Option<&'a str>,
+    condition: Condition,
+    temperature: Option<f64>,
+    apparent_temperature: Option<f64>,
+    humidity: Option<f64>,
+    pressure: Option<f64>,
+    wind_speed: Option<f64>,
+    wind_bearing: Option<f64>,
+    cloud_cover: Option<f64>,
+    precip_intensity: Option<f64>,
+    precip_probability: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Daily<'a> {
+    time: i64,
+    summary: Option<&'a str>,
+    condition: Condition,
+    temperature_high: Option<f64>,
+    temperature_low: Option<f64>,
+    apparent_temperature_high: Option<f64>,
+    apparent_temperature_low: Option<f64>,
+    humidity: Option<f64>,
+    pressure: Option<f64>,
+    wind_speed: Option<f64>,
+    wind_bearing: Option<f64>,
+    precip_intensity_max: Option<f64>,
+    precip_probability: Option<f64>,
+    uv_index: Option<f64>,
+    sunrise: Option<i64>,
+    sunset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Alert<'a> {
+    title: &'a str,
+    description: &'a str,
+    severity: Option<Severity>,
+    regions: &'a [String],
+    issued: i64,
+    expires: Option<i64>,
+    uri: Option<&'a str>,
+}
+
+/// Only the value, which is in the document's `units` once the report has been converted.
+fn value<U>(quantity: Option<Quantity<U>>) -> Option<f64> {
+    quantity.map(|quantity| quantity.value)
+}
+
+impl<'a> From<&'a Report> for Document<'a> {
+    fn from(report: &'a Report) -> Self {
+        let location = &report.location;
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            provider: report.provider,
+            location: Location {
+                name: location.name.as_deref(),
+                country: location.country.as_deref(),
+                latitude: location.latitude,
+                longitude: location.longitude,
+                timezone: location.timezone.as_deref(),
+            },
+            units: Units {
+                temperature: report.units.temperature,
+                speed: report.units.speed,
+                pressure: report.units.pressure,
+                distance: report.units.distance,
+                precipitation: report.units.precipitation,
+            },
+            current: report.current.as_ref().map(Current::from),
+            hourly: report.hourly.iter().map(Hourly::from).collect(),
+            daily: report.daily.iter().map(Daily::from).collect(),
+            alerts: report.alerts.iter().map(Alert::from).collect(),
+            age: report.age,
+            stale: report.stale,
+            failed: &report.failed,
+        }
+    }
+}
+
+impl<'a> From<&'a Observation> for Current<'a> {
+    fn from(current: &'a Observation) -> Self {
+        Self {
+            time: current.time,
+            summary: current.summary.as_deref(),
+            condition: current.condition,
+            temperature: value(current.temperature),
+            apparent_temperature: value(current.apparent_temperature),
+            dew_point: value(current.dew_point),
+            humidity: current.humidity,
+            pressure: value(current.pressure),
+            wind_speed: value(current.wind_speed),
+            wind_gust: value(current.wind_gust),
+            wind_bearing: current.wind_bearing,
+            cloud_cover: current.cloud_cover,
+            visibility: value(current.visibility),
+            uv_index: current.uv_index,
+            precip_intensity: value(current.precip_intensity),
+            precip_probability: current.precip_probability,
+            sunrise: current.sunrise,
+            sunset: current.sunset,
+        }
+    }
+}
+
+impl<'a> From<&'a HourlyPoint> for Hourly<'a> {
+    fn from(point: &'a HourlyPoint) -> Self {
+        Self {
+            time: point.time,
+            summary: point.summary.as_deref(),
+            condition: point.condition,
+            temperature: value(point.temperature),
+            apparent_temperature: value(point.apparent_temperature),
+            humidity: point.humidity,
+            pressure: value(point.pressure),
+            wind_speed: value(point.wind_speed),
+            wind_bearing: point.wind_bearing,
+            cloud_cover: point.cloud_cover,
+            precip_intensity: value(point.precip_intensity),
+            precip_probability: point.precip_probability,
+        }
+    }
+}
+
+impl<'a> From<&'a DailySummary> for Daily<'a> {
+    fn from(day: &'a DailySummary) -> Self {
+        Self {
+            time: day.time,
+            summary: day.summary.as_deref(),
+            condition: day.condition,
+            temperature_high: value(day.temperature_high),
+            temperature_low: value(day.temperature_low),
+            apparent_temperature_high: value(day.apparent_temperature_high),
+            apparent_temperature_low: value(day.apparent_temperature_low),
+            humidity: day.humidity,
+            pressure: value(day.pressure),
+            wind_speed: value(day.wind_speed),
+            wind_bearing: day.wind_bearing,
+            precip_intensity_max: value(day.precip_intensity_max),
+            precip_probability: day.precip_probability,
+            uv_index: day.uv_index,
+            sunrise: day.sunrise,
+            sunset: day.sunset,
+        }
+    }
+}
+
+impl<'a> From<&'a weather::Alert> for Alert<'a> {
+    fn from(alert: &'a weather::Alert) -> Self {
+        Self {
+            title: &alert.title,
+            description: &alert.description,
+            severity: alert.severity,
+            regions: &alert.regions,
+            issued: alert.issued,
+            expires: alert.expires,
+            uri: alert.uri.as_deref(),
+        }
+    }
+}
+
+pub fn json(reports: &[Report]) -> Result<String, Error> {
+    let documents: Vec<Document> = reports.iter().map(Document::from).collect();
+
+    Ok(serde_json::to_string_pretty(&documents)? + "\n")
+}
+
+pub fn ndjson(reports: &[Report]) -> Result<String, Error> {
+    let mut output = String::new();
+    for report in reports {
+        output += &serde_json::to_string(&Document::from(report))?;
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OwmUnit;
+    use crate::weather::{Distance, PrecipitationRate, Pressure, Speed, Temperature};
+
+    #[test]
+    fn it_keeps_the_documented_schema() {
+        let mut report = Report::new(Provider::Owm, Some(OwmUnit::Metric).into());
+        report.location.name = Some(String::from("Winnipeg"));
+        report.location.country = Some(String::from("CA"));
+        report.location.latitude = Some(49.9);
+        report.location.longitude = Some(-97.14);
+        report.location.timezone = Some(String::from("America/Winnipeg"));
+
+        let mut current = Observation::new(1_542_143_061);
+        current.summary = Some(String::from("clear sky"));
+        current.condition = Condition::ClearNight;
+        current.temperature = Some(Temperature::new(-3.5, TemperatureUnit::Celsius));
+        current.humidity = Some(0.74);
+        current.pressure = Some(Pressure::new(1021., PressureUnit::Hectopascals));
+        current.wind_speed = Some(Speed::new(4.1, SpeedUnit::MetresPerSecond));
+        current.wind_bearing = Some(310.);
+        current.visibility = Some(Distance::new(24.14, DistanceUnit::Kilometres));
+        current.sunrise = Some(1_542_116_053);
+        report.current = Some(current);
+
+        let mut hour = HourlyPoint::new(1_542_146_400);
+        hour.condition = Condition::Snow;
+        hour.temperature = Some(Temperature::new(-5., TemperatureUnit::Celsius));
+        hour.precip_probability = Some(0.8);
+        report.hourly = vec![hour];
+
+        let mut day = DailySummary::new(1_542_088_800);
+        day.temperature_high = Some(Temperature::new(-1., TemperatureUnit::Celsius));
+        day.precip_intensity_max = Some(PrecipitationRate::new(
+            0.5,
+            PrecipitationUnit::MillimetresPerHour,
+        ));
+        report.daily = vec![day];
+
+        report.alerts = vec![weather::Alert {
+            title: String::from("Winter Storm Warning"),
+            description: String::from("Heavy snow."),
+            severity: Some(Severity::Warning),
+            regions: vec![String::from("Winnipeg")],
+            issued: 1_542_100_000,
+            expires: None,
+            uri: None,
+        }];
+        report.age = Some(120);
+        report.failed = vec![Provider::DarkSky];
+
+        assert_eq!(
+            r#"[
+  {
+    "schema_version": 1,
+    "provider": "owm",
+    "location": {
+      "name": "Winnipeg",
+      "country": "CA",
+      "latitude": 49.9,
+      "longitude": -97.14,
+      "timezone": "America/Winnipeg"
+    },
+    "units": {
+      "temperature": "celsius",
+      "speed": "metres_per_second",
+      "pressure": "hectopascals",
+      "distance": "kilometres",
+      "precipitation": "millimetres_per_hour"
+    },
+    "current": {
+      "time": 1542143061,
+      "summary": "clear sky",
+      "condition": "clear-night",
+      "temperature": -3.5,
+      "apparent_temperature": null,
+      "dew_point": null,
+      "humidity": 0.74,
+      "pressure": 1021.0,
+      "wind_speed": 4.1,
+      "wind_gust": null,
+      "wind_bearing": 310.0,
+      "cloud_cover": null,
+      "visibility": 24.14,
+      "uv_index": null,
+      "precip_intensity": null,
+      "precip_probability": null,
+      "sunrise": 1542116053,
+      "sunset": null
+    },
+    "hourly": [
+      {
+        "time": 1542146400,
+        "summary": null,
+        "condition": "snow",
+        "temperature": -5.0,
+        "apparent_temperature": null,
+        "humidity": null,
+        "pressure": null,
+        "wind_speed": null,
+        "wind_bearing": null,
+        "cloud_cover": null,
+        "precip_intensity": null,
+        "precip_probability": 0.8
+      }
+    ],
+    "daily": [
+      {
+        "time": 1542088800,
+        "summary": null,
+        "condition": "unknown",
+        "temperature_high": -1.0,
+        "temperature_low": null,
+        "apparent_temperature_high": null,
+        "apparent_temperature_low": null,
+        "humidity": null,
+        "pressure": null,
+        "wind_speed": null,
+        "wind_bearing": null,
+        "precip_intensity_max": 0.5,
+        "precip_probability": null,
+        "uv_index": null,
+        "sunrise": null,
+        "sunset": null
+      }
+    ],
+    "alerts": [
+      {
+        "title": "Winter Storm Warning",
+        "description": "Heavy snow.",
+        "severity": "warning",
+        "regions": [
+          "Winnipeg"
+        ],
+        "issued": 1542100000,
+        "expires": null,
+        "uri": null
+      }
+    ],
+    "age": 120,
+    "stale": false,
+    "failed": [
+      "darksky"
+    ]
+  }
+]
+"#,
+            json(&[report]).unwrap()
+        );
+    }
+}
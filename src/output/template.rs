@@ -0,0 +1,377 @@
+use super::text::{clock, compass, duration};
+use crate::weather::{Quantity, Report, Unit};
+use failure::Fail;
+use std::str::FromStr;
+
+/// Every placeholder a template can use.
+pub const PLACEHOLDERS: &[&str] = &[
+    "provider",
+    "location",
+    "icon",
+    "condition",
+    "summary",
+    "temp",
+    "feels_like",
+    "dew_point",
+    "unit",
+    "humidity",
+    "pressure",
+    "pressure_unit",
+    "wind",
+    "wind_gust",
+    "wind_unit",
+    "wind_dir",
+    "wind_bearing",
+    "cloud_cover",
+    "visibility",
+    "distance_unit",
+    "uv_index",
+    "precip",
+    "precip_probability",
+    "precip_unit",
+    "sunrise",
+    "sunset",
+    "age",
+    "stale",
+];
+
+#[derive(Fail, Debug, PartialEq)]
+pub enum TemplateError {
+    #[fail(display = "unknown template placeholder {{{}}}", _0)]
+    UnknownPlaceholder(String),
+    #[fail(display = "invalid format spec \"{}\" for {{{}}}", _1, _0)]
+    InvalidSpec(String, String),
+    #[fail(display = "unmatched \"{}\" in template", _0)]
+    Unmatched(char),
+}
+
+/// A user-defined output format, e.g. `"{icon} {temp:.0}{unit} {summary}"`.
+///
+/// Placeholders are written as `{name}` or `{name:spec}`, where `spec` follows Rust's format
+/// syntax: `[[fill]align][width][.precision]`. The precision rounds numbers and truncates text.
+/// Use `{{` and `}}` for literal braces. Placeholders without a value render as empty strings.
+///
+/// Percentages (`humidity`, `cloud_cover`, `precip_probability`) go from 0 to 100, and `sunrise`
+/// and `sunset` are times of day at the location, like `07:34`. `stale` is the word "stale" when the provider
+/// couldn't be reached and old data was used, and `age` is how old cached data is, like `12m`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(String, Spec),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Spec {
+    fill: char,
+    align: Option<Align>,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+enum Value {
+    Number(f64),
+    Text(String),
+}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => return Err(TemplateError::Unmatched('}')),
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => return Err(TemplateError::Unmatched('{')),
+                        }
+                    }
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(literal.split_off(0)));
+                    }
+
+                    let mut parts = placeholder.splitn(2, ':');
+                    let name = parts.next().unwrap_or_default().trim();
+                    if !PLACEHOLDERS.contains(&name) {
+                        return Err(TemplateError::UnknownPlaceholder(name.to_string()));
+                    }
+
+                    let spec = parts.next().unwrap_or_default();
+                    let spec = spec.parse::<Spec>().map_err(|_| {
+                        TemplateError::InvalidSpec(name.to_string(), spec.to_string())
+                    })?;
+
+                    segments.push(Segment::Placeholder(name.to_string(), spec));
+                }
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+impl Template {
+    pub fn render(&self, report: &Report) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => literal.clone(),
+                Segment::Placeholder(name, spec) => spec.apply(value(name, report)),
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Spec {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let align = |c| match c {
+            '<' => Some(Align::Left),
+            '^' => Some(Align::Center),
+            '>' => Some(Align::Right),
+            _ => None,
+        };
+
+        let chars: Vec<char> = s.chars().collect();
+        let mut spec = Spec {
+            fill: ' ',
+            align: None,
+            width: None,
+            precision: None,
+        };
+
+        let mut rest = match chars.as_slice() {
+            [fill, a, rest @ ..] if align(*a).is_some() => {
+                spec.fill = *fill;
+                spec.align = align(*a);
+                rest
+            }
+            [a, rest @ ..] if align(*a).is_some() => {
+                spec.align = align(*a);
+                rest
+            }
+            rest => rest,
+        };
+
+        let digits = |rest: &[char]| -> (Option<usize>, usize) {
+            let len = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+            let number: String = rest[..len].iter().collect();
+            (number.parse().ok(), len)
+        };
+
+        let (width, len) = digits(rest);
+        spec.width = width;
+        rest = &rest[len..];
+
+        if let Some(('.', precision)) = rest.split_first().map(|(c, rest)| (*c, rest)) {
+            let (precision, len) = digits(precision);
+            if precision.is_none() {
+                return Err(());
+            }
+            spec.precision = precision;
+            rest = &rest[len + 1..];
+        }
+
+        if rest.is_empty() {
+            Ok(spec)
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl Spec {
+    fn apply(&self, value: Option<Value>) -> String {
+        let (text, default_align) = match value {
+            Some(Value::Number(number)) => match self.precision {
+                Some(precision) => (format!("{:.*}", precision, number), Align::Right),
+                None => (number.to_string(), Align::Right),
+            },
+            Some(Value::Text(text)) => match self.precision {
+                Some(precision) => (text.chars().take(precision).collect(), Align::Left),
+                None => (text, Align::Left),
+            },
+            None => (String::new(), Align::Left),
+        };
+
+        let padding = self
+            .width
+            .unwrap_or_default()
+            .saturating_sub(text.chars().count());
+        let fill = |count| std::iter::repeat_n(self.fill, count).collect::<String>();
+
+        match self.align.unwrap_or(default_align) {
+            Align::Left => text + &fill(padding),
+            Align::Right => fill(padding) + &text,
+            Align::Center => fill(padding / 2) + &text + &fill(padding - padding / 2),
+        }
+    }
+}
+
+fn value(name: &str, report: &Report) -> Option<Value> {
+    let units = &report.units;
+    let text = |text: &str| Some(Value::Text(text.to_string()));
+
+    match name {
+        "provider" => return text(report.provider.name()),
+        "location" => return report.location.name.clone().map(Value::Text),
+        "unit" => return text(units.temperature.symbol()),
+        "pressure_unit" => return text(units.pressure.symbol()),
+        "wind_unit" => return text(units.speed.symbol()),
+        "distance_unit" => return text(units.distance.symbol()),
+        "precip_unit" => return text(units.precipitation.symbol()),
+        "age" => return report.age.map(|age| Value::Text(duration(age))),
+        "stale" if report.stale => return text("stale"),
+        "stale" => return None,
+        _ => {}
+    }
+
+    let current = report.current.as_ref()?;
+    let percent = |ratio: Option<f64>| ratio.map(|ratio| Value::Number(ratio * 100.));
+
+    match name {
+        "icon" => text(current.condition.icon()),
+        "condition" => Some(Value::Text(current.condition.to_string())),
+        "summary" => current
+            .summary
+            .clone()
+            .or_else(|| Some(current.condition.to_string()))
+            .map(Value::Text),
+        "temp" => number(current.temperature, units.temperature),
+        "feels_like" => number(current.apparent_temperature, units.temperature),
+        "dew_point" => number(current.dew_point, units.temperature),
+        "humidity" => percent(current.humidity),
+        "pressure" => number(current.pressure, units.pressure),
+        "wind" => number(current.wind_speed, units.speed),
+        "wind_gust" => number(current.wind_gust, units.speed),
+        "wind_dir" => current
+            .wind_bearing
+            .and_then(|bearing| text(compass(bearing))),
+        "wind_bearing" => current.wind_bearing.map(Value::Number),
+        "cloud_cover" => percent(current.cloud_cover),
+        "visibility" => number(current.visibility, units.distance),
+        "uv_index" => current.uv_index.map(Value::Number),
+        "precip" => number(current.precip_intensity, units.precipitation),
+        "precip_probability" => percent(current.precip_probability),
+        "sunrise" => current
+            .sunrise
+            .map(|time| Value::Text(clock(time, &report.location))),
+        "sunset" => current
+            .sunset
+            .map(|time| Value::Text(clock(time, &report.location))),
+        _ => None,
+    }
+}
+
+/// A measurement in the unit its placeholder says it's in.
+fn number<U: Unit>(quantity: Option<Quantity<U>>, unit: U) -> Option<Value> {
+    quantity.map(|quantity| Value::Number(quantity.value_in(unit)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OwmUnit;
+    use crate::weather::{Condition, Observation, Temperature, TemperatureUnit};
+    use crate::weather_api::Provider;
+
+    fn report() -> Report {
+        let mut report = Report::new(Provider::Owm, Some(OwmUnit::Metric).into());
+        let mut current = Observation::new(0);
+        current.condition = Condition::Cloudy;
+        current.summary = Some(String::from("overcast clouds"));
+        current.temperature = Some(Temperature::new(-3.46, TemperatureUnit::Celsius));
+        current.humidity = Some(0.746);
+        report.current = Some(current);
+
+        report
+    }
+
+    fn render(template: &str) -> String {
+        template.parse::<Template>().unwrap().render(&report())
+    }
+
+    #[test]
+    fn it_renders_placeholders() {
+        assert_eq!(
+            "☁ -3°C overcast clouds",
+            render("{icon} {temp:.0}{unit} {summary}")
+        );
+        assert_eq!("-3.46 | 75%", render("{temp} | {humidity:.0}%"));
+    }
+
+    #[test]
+    fn it_pads_and_truncates() {
+        assert_eq!("[  -3.5]", render("[{temp:6.1}]"));
+        assert_eq!("[-3.5  ]", render("[{temp:<6.1}]"));
+        assert_eq!("[**over**]", render("[{summary:*^8.4}]"));
+        assert_eq!("[overcast  ]", render("[{summary:10.8}]"));
+    }
+
+    #[test]
+    fn it_renders_missing_values_as_empty() {
+        assert_eq!("wind: ", render("wind: {wind}"));
+    }
+
+    #[test]
+    fn it_escapes_braces() {
+        assert_eq!("{-3}", render("{{{temp:.0}}}"));
+    }
+
+    #[test]
+    fn it_rejects_bad_templates() {
+        assert_eq!(
+            Err(TemplateError::UnknownPlaceholder(String::from("tmp"))),
+            "{tmp}".parse::<Template>()
+        );
+        assert_eq!(
+            Err(TemplateError::InvalidSpec(
+                String::from("temp"),
+                String::from(".x")
+            )),
+            "{temp:.x}".parse::<Template>()
+        );
+        assert_eq!(
+            Err(TemplateError::Unmatched('{')),
+            "{temp".parse::<Template>()
+        );
+        assert_eq!(
+            Err(TemplateError::Unmatched('}')),
+            "temp}".parse::<Template>()
+        );
+    }
+}
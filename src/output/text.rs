@@ -0,0 +1,248 @@
+use crate::weather::{Location, Observation, Report, Unit};
+use chrono::{Local, TimeZone};
+use std::fmt;
+
+/// Human-readable current conditions, meant for a terminal.
+///
+/// ```text
+/// Winnipeg, CA (OpenWeatherMap)
+/// clear sky
+///   Temperature  -3.5°C (feels like -8.2°C)
+///   Humidity     74%
+///   Wind         4.1 m/s NW
+///   Pressure     1021 hPa
+///   Sunrise      07:34
+///   Sunset       16:23
+/// ```
+pub struct CurrentConditions<'a>(pub &'a Report);
+
+impl<'a> fmt::Display for CurrentConditions<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let report = self.0;
+        writeln!(f, "{}", heading(report))?;
+
+        let current = match &report.current {
+            Some(current) => current,
+            None => return write!(f, "No current conditions available"),
+        };
+
+        match &current.summary {
+            Some(summary) => writeln!(f, "{}", summary)?,
+            None => writeln!(f, "{}", current.condition)?,
+        }
+
+        for (label, value) in rows(report, current) {
+            writeln!(f, "  {:<12} {}", label, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn heading(report: &Report) -> String {
+    let place = place(report);
+
+    if report.stale {
+        let age = report.age.map(duration).unwrap_or_default();
+        format!(
+            "{} ({}) [stale, {} old]",
+            place,
+            report.provider.name(),
+            age
+        )
+    } else {
+        format!("{} ({})", place, report.provider.name())
+    }
+}
+
+/// Where the report is for, by name when the provider gave one.
+pub(crate) fn place(report: &Report) -> String {
+    let location = &report.location;
+    match (&location.name, &location.country) {
+        (Some(name), Some(country)) => format!("{}, {}", name, country),
+        (Some(name), None) => name.clone(),
+        _ => match (location.latitude, location.longitude) {
+            (Some(lat), Some(lon)) => format!("{}, {}", lat, lon),
+            _ => String::from("Unknown location"),
+        },
+    }
+}
+
+fn rows(report: &Report, current: &Observation) -> Vec<(&'static str, String)> {
+    let units = &report.units;
+    let mut rows = Vec::new();
+
+    if let Some(temperature) = current.temperature {
+        let unit = units.temperature;
+        let mut value = format!("{:.1}{}", temperature.value_in(unit), unit.symbol());
+        if let Some(feels_like) = current.apparent_temperature {
+            value += &format!(
+                " (feels like {:.1}{})",
+                feels_like.value_in(unit),
+                unit.symbol()
+            );
+        }
+        rows.push(("Temperature", value));
+    }
+
+    if let Some(humidity) = current.humidity {
+        rows.push(("Humidity", format!("{:.0}%", humidity * 100.)));
+    }
+
+    if let Some(speed) = current.wind_speed {
+        let unit = units.speed;
+        let mut value = format!("{:.1} {}", speed.value_in(unit), unit.symbol());
+        if let Some(bearing) = current.wind_bearing {
+            value += &format!(" {}", compass(bearing));
+        }
+        if let Some(gust) = current.wind_gust {
+            value += &format!(" (gusts {:.1} {})", gust.value_in(unit), unit.symbol());
+        }
+        rows.push(("Wind", value));
+    }
+
+    if let Some(pressure) = current.pressure {
+        rows.push((
+            "Pressure",
+            format!(
+                "{:.0} {}",
+                pressure.value_in(units.pressure),
+                units.pressure.symbol()
+            ),
+        ));
+    }
+
+    if let Some(sunrise) = current.sunrise {
+        rows.push(("Sunrise", clock(sunrise, &report.location)));
+    }
+
+    if let Some(sunset) = current.sunset {
+        rows.push(("Sunset", clock(sunset, &report.location)));
+    }
+
+    rows
+}
+
+/// The 16-point compass direction the wind is blowing from.
+pub(crate) fn compass(bearing: f64) -> &'static str {
+    const POINTS: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW",
+        "NW", "NNW",
+    ];
+
+    let index = (bearing.rem_euclid(360.) / 22.5).round() as usize % POINTS.len();
+    POINTS[index]
+}
+
+/// A rough, short duration like `45s`, `12m`, `3h` or `2d`.
+pub(crate) fn duration(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        3600..=86_399 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86_400),
+    }
+}
+
+/// The time of day at `location`, or on this machine when its time zone isn't known.
+pub(crate) fn clock(time: i64, location: &Location) -> String {
+    let time = match (location.time_zone(), location.utc_offset) {
+        (Some(tz), _) => time_of_day(time, &tz),
+        (None, Some(offset)) => time_of_day(time, &offset),
+        (None, None) => time_of_day(time, &Local),
+    };
+
+    time.unwrap_or_else(|| String::from("--:--"))
+}
+
+fn time_of_day<Tz: TimeZone>(time: i64, tz: &Tz) -> Option<String>
+where
+    Tz::Offset: fmt::Display,
+{
+    tz.timestamp_opt(time, 0)
+        .single()
+        .map(|time| time.format("%H:%M").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DarkSkyUnit;
+    use crate::weather::{
+        Condition, Pressure, PressureUnit, Speed, SpeedUnit, Temperature, TemperatureUnit, Units,
+    };
+    use crate::weather_api::Provider;
+
+    #[test]
+    fn it_finds_the_compass_point() {
+        assert_eq!("N", compass(0.));
+        assert_eq!("N", compass(355.));
+        assert_eq!("ENE", compass(67.5));
+        assert_eq!("W", compass(270.));
+        assert_eq!("NNW", compass(-20.));
+    }
+
+    #[test]
+    fn it_shows_times_at_the_location() {
+        let winnipeg = Location {
+            timezone: Some(String::from("America/Winnipeg")),
+            ..Default::default()
+        };
+        let tokyo = Location {
+            timezone: Some(String::from("Asia/Tokyo")),
+            ..Default::default()
+        };
+
+        assert_eq!("07:34", clock(1_542_116_053, &winnipeg));
+        assert_eq!("22:34", clock(1_542_116_053, &tokyo));
+    }
+
+    #[test]
+    fn it_shortens_durations() {
+        assert_eq!("45s", duration(45));
+        assert_eq!("12m", duration(12 * 60 + 5));
+        assert_eq!("3h", duration(3 * 3600 + 59 * 60));
+        assert_eq!("2d", duration(2 * 86_400));
+    }
+
+    #[test]
+    fn it_marks_stale_conditions() {
+        let mut report = Report::new(Provider::Owm, Units::from(None));
+        report.location.name = Some(String::from("Winnipeg"));
+        report.age = Some(2 * 3600);
+        report.stale = true;
+
+        assert_eq!(
+            "Winnipeg (OpenWeatherMap) [stale, 2h old]\n\
+             No current conditions available",
+            CurrentConditions(&report).to_string()
+        );
+    }
+
+    #[test]
+    fn it_renders_current_conditions() {
+        let mut report = Report::new(Provider::DarkSky, Units::from(DarkSkyUnit::Ca));
+        report.location.name = Some(String::from("Winnipeg"));
+        report.location.country = Some(String::from("CA"));
+
+        let mut current = Observation::new(0);
+        current.condition = Condition::Cloudy;
+        current.temperature = Some(Temperature::new(-3.54, TemperatureUnit::Celsius));
+        current.apparent_temperature = Some(Temperature::new(-8.2, TemperatureUnit::Celsius));
+        current.humidity = Some(0.74);
+        current.wind_speed = Some(Speed::new(15., SpeedUnit::KilometresPerHour));
+        current.wind_bearing = Some(310.);
+        current.pressure = Some(Pressure::new(1021.3, PressureUnit::Hectopascals));
+        report.current = Some(current);
+
+        assert_eq!(
+            "Winnipeg, CA (Dark Sky)\n\
+             cloudy\n  \
+             Temperature  -3.5°C (feels like -8.2°C)\n  \
+             Humidity     74%\n  \
+             Wind         15.0 km/h NW\n  \
+             Pressure     1021 hPa\n",
+            CurrentConditions(&report).to_string()
+        );
+    }
+}
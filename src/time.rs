@@ -0,0 +1,317 @@
+//! Parsing of the times given to `--time`.
+//!
+//! Besides Unix timestamps and ISO-8601, times can be relative to now (`-3d`, `+12h`, `-1d6h`,
+//! `3 days ago`) or a day and a time of day:
+//!
+//! * days: `today`, `yesterday`, `tomorrow`, `friday`, `last friday`, `next friday` or
+//!   `2026-10-01`
+//! * times: `15:00`, `15:00:30`, `3pm`, `3:30pm`, `noon` or `midnight`
+//!
+//! e.g. `yesterday 15:00`, `last friday noon` or `9am`. A day on its own means midnight, and a
+//! time on its own means today.
+
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use failure::Fail;
+
+#[derive(Fail, Debug, PartialEq)]
+#[fail(
+    display = "invalid time \"{}\". Use a Unix timestamp, an ISO-8601 date and time, \
+               or something like \"-3d\" or \"yesterday 15:00\"",
+    _0
+)]
+pub struct InvalidTime(pub String);
+
+/// Parse a time, as a Unix timestamp.
+///
+/// Times without an offset are in `tz`, which should be the location's time zone.
+pub fn parse(s: &str, tz: Tz) -> Result<i64, InvalidTime> {
+    parse_at(s, &Utc::now().with_timezone(&tz))
+}
+
+/// Parse a time like [`parse`], and give the day it's on in `tz`.
+pub fn parse_date(s: &str, tz: Tz) -> Result<NaiveDate, InvalidTime> {
+    let time = parse(s, tz)?;
+    tz.timestamp_opt(time, 0)
+        .single()
+        .map(|time| time.date_naive())
+        .ok_or_else(|| InvalidTime(s.to_string()))
+}
+
+fn parse_at<Zone: TimeZone>(s: &str, now: &DateTime<Zone>) -> Result<i64, InvalidTime> {
+    let s = s.trim();
+    let invalid = || InvalidTime(s.to_string());
+
+    if let Ok(timestamp) = s.parse::<i64>() {
+        return Ok(timestamp);
+    }
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Ok(time.timestamp());
+    }
+
+    let lower = s.to_lowercase();
+    if let Some(offset) = relative(&lower) {
+        return now
+            .clone()
+            .checked_add_signed(offset)
+            .map(|time| time.timestamp())
+            .ok_or_else(invalid);
+    }
+
+    absolute(s, now.naive_local())
+        .and_then(|naive| now.timezone().from_local_datetime(&naive).earliest())
+        .map(|time| time.timestamp())
+        .ok_or_else(invalid)
+}
+
+/// `-3d`, `+1d12h` or `3 days ago`.
+fn relative(s: &str) -> Option<Duration> {
+    let words: Vec<&str> = s.split_whitespace().collect();
+    if let [amount, unit, "ago"] = words[..] {
+        let seconds = amount.parse::<i64>().ok()?.checked_mul(seconds(unit)?)?;
+        return Duration::try_seconds(-seconds);
+    }
+
+    let (sign, mut rest) = match s.chars().next()? {
+        '-' => (-1, &s[1..]),
+        '+' => (1, &s[1..]),
+        _ => return None,
+    };
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut total = 0_i64;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let letters = rest[digits..]
+            .find(|c: char| c.is_ascii_digit())
+            .map_or(rest.len(), |i| digits + i);
+        if digits == 0 {
+            return None;
+        }
+
+        let amount = rest[..digits].parse::<i64>().ok()?;
+        total = total.checked_add(amount.checked_mul(seconds(&rest[digits..letters])?)?)?;
+        rest = &rest[letters..];
+    }
+
+    Duration::try_seconds(sign * total)
+}
+
+fn seconds(unit: &str) -> Option<i64> {
+    match unit {
+        "s" | "sec" | "secs" | "second" | "seconds" => Some(1),
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(60),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(60 * 60),
+        "d" | "day" | "days" => Some(24 * 60 * 60),
+        "w" | "week" | "weeks" => Some(7 * 24 * 60 * 60),
+        _ => None,
+    }
+}
+
+/// An ISO-8601 time without an offset, or a day and/or a time of day.
+fn absolute(s: &str, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let iso = [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok());
+    if iso.is_some() {
+        return iso;
+    }
+
+    let lower = s.to_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    if words[..] == ["now"] {
+        return Some(now);
+    }
+
+    let today = now.date();
+    let (date, rest) = match words[..] {
+        ["today", ref rest @ ..] => (Some(today), rest),
+        ["yesterday", ref rest @ ..] => (today.pred_opt(), rest),
+        ["tomorrow", ref rest @ ..] => (today.succ_opt(), rest),
+        ["last", day, ref rest @ ..] => (weekday_before(today, day.parse().ok()?), rest),
+        ["next", day, ref rest @ ..] => (weekday_after(today, day.parse().ok()?), rest),
+        [day, ref rest @ ..] if day.parse::<Weekday>().is_ok() => {
+            let weekday = day.parse().ok()?;
+            if today.weekday() == weekday {
+                (Some(today), rest)
+            } else {
+                (weekday_before(today, weekday), rest)
+            }
+        }
+        [day, ref rest @ ..] if NaiveDate::parse_from_str(day, "%Y-%m-%d").is_ok() => {
+            (NaiveDate::parse_from_str(day, "%Y-%m-%d").ok(), rest)
+        }
+        ref rest => (None, rest),
+    };
+
+    match (date, rest) {
+        (None, []) => None,
+        (Some(date), []) => date.and_hms_opt(0, 0, 0),
+        (date, rest) => Some(date.unwrap_or(today).and_time(time_of_day(&rest.concat())?)),
+    }
+}
+
+fn weekday_before(date: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    let days = (7 + date.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+    date.checked_sub_signed(Duration::days(if days == 0 { 7 } else { i64::from(days) }))
+}
+
+fn weekday_after(date: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    let days = (7 + weekday.num_days_from_monday() - date.weekday().num_days_from_monday()) % 7;
+    date.checked_add_signed(Duration::days(if days == 0 { 7 } else { i64::from(days) }))
+}
+
+/// `15:00`, `15:00:30`, `3pm`, `3:30pm`, `noon` or `midnight`.
+fn time_of_day(s: &str) -> Option<NaiveTime> {
+    match s {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+
+    let (clock, afternoon) = if let Some(clock) = s.strip_suffix("am") {
+        (clock, Some(false))
+    } else if let Some(clock) = s.strip_suffix("pm") {
+        (clock, Some(true))
+    } else {
+        (s, None)
+    };
+
+    let mut parts = clock.splitn(3, ':');
+    let hour = parts.next()?.parse::<u32>().ok()?;
+    let minute = parts.next().map(str::parse::<u32>);
+    let second = parts.next().map(str::parse::<u32>);
+
+    let (minute, second) = match (minute, second, afternoon) {
+        // A bare number is only a time with am or pm.
+        (None, _, None) => return None,
+        (None, _, Some(_)) => (0, 0),
+        (Some(minute), None, _) => (minute.ok()?, 0),
+        (Some(minute), Some(second), _) => (minute.ok()?, second.ok()?),
+    };
+
+    let hour = match afternoon {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some(false) => hour % 12,
+        Some(true) => hour % 12 + 12,
+        None => hour,
+    };
+
+    NaiveTime::from_hms_opt(hour, minute, second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tuesday, 13 November 2018, 15:00 in Winnipeg.
+    fn now() -> DateTime<Tz> {
+        chrono_tz::America::Winnipeg
+            .with_ymd_and_hms(2018, 11, 13, 15, 0, 0)
+            .unwrap()
+    }
+
+    fn local(s: &str) -> i64 {
+        chrono_tz::America::Winnipeg
+            .from_local_datetime(&NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap())
+            .unwrap()
+            .timestamp()
+    }
+
+    #[test]
+    fn it_parses_unix_timestamps() {
+        assert_eq!(Ok(1_542_143_061), parse_at("1542143061", &now()));
+        assert_eq!(Ok(-1), parse_at("-1", &now()));
+    }
+
+    #[test]
+    fn it_parses_iso_8601() {
+        assert_eq!(Ok(1_542_121_200), parse_at("2018-11-13T15:00:00Z", &now()));
+        assert_eq!(
+            Ok(1_542_121_200),
+            parse_at("2018-11-13T09:00:00-06:00", &now())
+        );
+        assert_eq!(Ok(1_542_121_200), parse_at("2018-11-13T09:00", &now()));
+        assert_eq!(Ok(1_542_121_200), parse_at("2018-11-13 09:00:00", &now()));
+        assert_eq!(
+            Ok(local("2018-11-13 00:00")),
+            parse_at("2018-11-13", &now())
+        );
+    }
+
+    #[test]
+    fn it_parses_relative_times() {
+        let time = now().timestamp();
+        assert_eq!(Ok(time - 3 * 86_400), parse_at("-3d", &now()));
+        assert_eq!(Ok(time + 12 * 3600), parse_at("+12h", &now()));
+        assert_eq!(Ok(time - 86_400 - 6 * 3600), parse_at("-1d6h", &now()));
+        assert_eq!(Ok(time - 2 * 604_800), parse_at("2 weeks ago", &now()));
+        assert_eq!(Ok(time - 90 * 60), parse_at("90 minutes ago", &now()));
+    }
+
+    #[test]
+    fn it_parses_days_and_times() {
+        assert_eq!(Ok(now().timestamp()), parse_at("now", &now()));
+        assert_eq!(Ok(local("2018-11-13 00:00")), parse_at("today", &now()));
+        assert_eq!(
+            Ok(local("2018-11-12 15:00")),
+            parse_at("yesterday 15:00", &now())
+        );
+        assert_eq!(
+            Ok(local("2018-11-14 09:30")),
+            parse_at("Tomorrow 9:30am", &now())
+        );
+        assert_eq!(Ok(local("2018-11-13 21:00")), parse_at("9 pm", &now()));
+        assert_eq!(Ok(local("2018-11-13 00:00")), parse_at("tuesday", &now()));
+        assert_eq!(
+            Ok(local("2018-11-06 00:00")),
+            parse_at("last tuesday", &now())
+        );
+        assert_eq!(
+            Ok(local("2018-11-09 12:00")),
+            parse_at("last friday noon", &now())
+        );
+        assert_eq!(Ok(local("2018-11-09 00:00")), parse_at("fri", &now()));
+        assert_eq!(
+            Ok(local("2018-11-19 00:00")),
+            parse_at("next monday midnight", &now())
+        );
+        assert_eq!(
+            Ok(local("2018-10-01 08:00")),
+            parse_at("2018-10-01 8am", &now())
+        );
+    }
+
+    #[test]
+    fn it_rejects_other_times() {
+        for time in &[
+            "tomorrow-ish",
+            "",
+            "-",
+            "-3x",
+            "today 15",
+            "13pm",
+            "last",
+            "yesterday 25:00",
+        ] {
+            assert_eq!(
+                Err(InvalidTime(time.to_string())),
+                parse_at(time, &now()),
+                "{}",
+                time
+            );
+        }
+    }
+}
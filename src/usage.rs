@@ -0,0 +1,444 @@
+//! A ledger of the calls made to each provider, under `$XDG_DATA_HOME/nimbus-alt/usage.json`.
+
+use crate::config::Config;
+use crate::weather_api::Provider;
+use chrono::{Datelike, NaiveDate, Utc};
+use failure::{Error, Fail};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(Fail, Debug)]
+#[fail(display = "{}'s daily budget is used up, with {} calls today", _0, _1)]
+pub struct BudgetReached(&'static str, u64);
+
+#[derive(Fail, Debug)]
+#[fail(display = "another run has been holding {} for too long", _0)]
+pub struct LedgerLocked(String);
+
+/// Calls made today and this month, per provider and key, and the daily budgets they count
+/// against.
+///
+/// Days are in UTC, which is when the providers' daily limits start over.
+///
+/// The file is read again every time it's used, while holding a lock, since other runs (like a
+/// status bar's) may be counting calls at the same time.
+#[derive(Debug)]
+pub struct Ledger<'a> {
+    path: PathBuf,
+    config: &'a Config,
+}
+
+/// The calls made to a provider with a key on a day.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct Entry {
+    provider: Provider,
+    /// The end of the API key, to tell keys apart without storing them.
+    key: Option<String>,
+    date: String,
+    calls: u64,
+    /// The day's calls as the provider counts them, when it says (DarkSky's
+    /// `X-Forecast-API-Calls`), which includes calls made from elsewhere.
+    reported: Option<u64>,
+}
+
+impl Entry {
+    fn is(&self, provider: Provider, key: Option<&str>, date: &str) -> bool {
+        self.provider == provider && self.key == key.map(hint) && self.date == date
+    }
+
+    /// The day's calls, trusting the provider's count over ours when it has one.
+    fn today(&self) -> u64 {
+        self.reported
+            .map_or(self.calls, |reported| reported.max(self.calls))
+    }
+}
+
+/// The usage of one provider with one key.
+#[derive(Debug, PartialEq)]
+pub struct Usage {
+    pub provider: Provider,
+    pub key: Option<String>,
+    pub today: u64,
+    pub month: u64,
+    pub budget: Option<u64>,
+}
+
+/// Held while the ledger is read and written. It's a file next to the ledger, which only one run
+/// can create at a time, and it's removed when dropped.
+struct Lock(PathBuf);
+
+impl Lock {
+    /// How long to wait for another run to finish with the ledger.
+    const PATIENCE: Duration = Duration::from_secs(5);
+    /// A lock older than this was left behind by a run that crashed.
+    const ABANDONED: Duration = Duration::from_secs(30);
+
+    fn acquire(path: PathBuf) -> Result<Self, Error> {
+        let start = Instant::now();
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Lock(path)),
+                Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e.into()),
+            }
+
+            let abandoned = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_some_and(|age| age > Self::ABANDONED);
+            if abandoned {
+                warn!("removing abandoned lock {}", path.display());
+                let _ = fs::remove_file(&path);
+            } else if start.elapsed() > Self::PATIENCE {
+                return Err(LedgerLocked(path.display().to_string()).into());
+            } else {
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+impl<'a> Ledger<'a> {
+    /// Returns `None` when there's no data directory to keep the ledger in.
+    pub fn new(config: &'a Config) -> Option<Self> {
+        let path = match dirs::data_dir() {
+            Some(dir) => dir.join(crate_name!()).join("usage.json"),
+            None => {
+                warn!("couldn't find XDG_DATA_HOME, not keeping track of API usage");
+                return None;
+            }
+        };
+
+        Some(Self::open(path, config))
+    }
+
+    fn open(path: PathBuf, config: &'a Config) -> Self {
+        Self { path, config }
+    }
+
+    fn lock(&self) -> Result<Lock, Error> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        Lock::acquire(self.path.with_extension("json.lock"))
+    }
+
+    fn read(&self) -> Vec<Entry> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Replace the ledger all at once, so it's never seen half written.
+    fn write(&self, entries: &[Entry]) -> Result<(), Error> {
+        let temporary = self.path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_string(entries)?)?;
+        fs::rename(&temporary, &self.path)?;
+
+        Ok(())
+    }
+
+    /// The configured daily budget for a provider.
+    pub fn budget(&self, provider: Provider) -> Option<u64> {
+        match provider {
+            Provider::DarkSky => self.config.darksky.as_ref()?.daily_budget,
+            Provider::Owm | Provider::OwmOneCall => self.config.owm.as_ref()?.daily_budget,
+            _ => None,
+        }
+    }
+
+    /// An error when the provider's daily budget has been used up with this key.
+    pub fn check(&self, provider: Provider, key: Option<&str>) -> Result<(), BudgetReached> {
+        let budget = match self.budget(provider) {
+            Some(budget) => budget,
+            None => return Ok(()),
+        };
+
+        // The ledger is only ever replaced whole, so it can still be read without the lock.
+        let _lock = self
+            .lock()
+            .map_err(|e| warn!("reading the API usage without a lock: {}", e))
+            .ok();
+        let today = self
+            .read()
+            .iter()
+            .find(|entry| entry.is(provider, key, &today()))
+            .map_or(0, Entry::today);
+
+        if today >= budget {
+            Err(BudgetReached(provider.name(), today))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Count a call, along with the provider's own count of the day's calls when it gave one.
+    pub fn record(
+        &self,
+        provider: Provider,
+        key: Option<&str>,
+        reported: Option<u64>,
+    ) -> Result<(), Error> {
+        self.record_on(&today(), provider, key, reported)
+    }
+
+    fn record_on(
+        &self,
+        date: &str,
+        provider: Provider,
+        key: Option<&str>,
+        reported: Option<u64>,
+    ) -> Result<(), Error> {
+        let _lock = self.lock()?;
+        let mut entries = self.read();
+        match entries
+            .iter_mut()
+            .find(|entry| entry.is(provider, key, date))
+        {
+            Some(entry) => {
+                entry.calls += 1;
+                entry.reported = reported.or(entry.reported);
+            }
+            None => entries.push(Entry {
+                provider,
+                key: key.map(hint),
+                date: date.to_string(),
+                calls: 1,
+                reported,
+            }),
+        }
+
+        // Nothing's shown from before this month, or from dates that don't make sense.
+        let this_month = month(date);
+        entries.retain(|entry| month(&entry.date).is_some() && month(&entry.date) >= this_month);
+
+        self.write(&entries)
+    }
+
+    /// The usage of each provider and key that's been called this month.
+    pub fn usage(&self) -> Vec<Usage> {
+        self.usage_on(&today())
+    }
+
+    fn usage_on(&self, date: &str) -> Vec<Usage> {
+        let this_month = month(date);
+        let mut usage: Vec<Usage> = Vec::new();
+
+        for entry in self
+            .read()
+            .iter()
+            .filter(|entry| this_month.is_some() && month(&entry.date) == this_month)
+        {
+            let today = if entry.date == date { entry.today() } else { 0 };
+            match usage
+                .iter_mut()
+                .find(|usage| usage.provider == entry.provider && usage.key == entry.key)
+            {
+                Some(usage) => {
+                    usage.today += today;
+                    usage.month += entry.today();
+                }
+                None => usage.push(Usage {
+                    provider: entry.provider,
+                    key: entry.key.clone(),
+                    today,
+                    month: entry.today(),
+                    budget: self.budget(entry.provider),
+                }),
+            }
+        }
+
+        usage
+    }
+}
+
+/// The usage as a table, for the `usage` command.
+///
+/// ```text
+/// Provider                  Key     Today  This month  Budget
+/// Dark Sky                  …p3q4      12         340    1000
+/// OpenWeatherMap            …c3d4       3          80
+/// ```
+pub struct UsageTable<'a>(pub &'a [Usage]);
+
+impl<'a> fmt::Display for UsageTable<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return writeln!(f, "No calls this month");
+        }
+
+        writeln!(
+            f,
+            "{:<24}  {:<6} {:>6}  {:>10}  {:>6}",
+            "Provider", "Key", "Today", "This month", "Budget"
+        )?;
+        for usage in self.0 {
+            let budget = usage.budget.map(|budget| budget.to_string());
+            let line = format!(
+                "{:<24}  {:<6} {:>6}  {:>10}  {:>6}",
+                usage.provider.name(),
+                usage.key.as_deref().unwrap_or("-"),
+                usage.today,
+                usage.month,
+                budget.unwrap_or_default()
+            );
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The last few characters of an API key.
+fn hint(key: &str) -> String {
+    let start = key.char_indices().rev().nth(3).map_or(0, |(i, _)| i);
+    format!("…{}", &key[start..])
+}
+
+fn today() -> String {
+    Utc::now().date_naive().to_string()
+}
+
+/// The year and month of a `YYYY-MM-DD` date, or `None` when it isn't one.
+fn month(date: &str) -> Option<(i32, u32)> {
+    let date = date.parse::<NaiveDate>().ok()?;
+    Some((date.year(), date.month()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DarkSkyConfig;
+
+    fn ledger<'a>(name: &str, config: &'a Config) -> Ledger<'a> {
+        let path = std::env::temp_dir().join(format!("{}-usage-{}.json", crate_name!(), name));
+        let _ = fs::remove_file(&path);
+
+        Ledger::open(path, config)
+    }
+
+    #[test]
+    fn it_counts_calls_per_provider_and_key() {
+        let config = Config::default();
+        let ledger = ledger("count", &config);
+
+        ledger
+            .record_on("2018-10-31", Provider::DarkSky, Some("n1o2p3q4"), None)
+            .unwrap();
+        for _ in 0..2 {
+            ledger
+                .record_on("2018-11-12", Provider::DarkSky, Some("n1o2p3q4"), None)
+                .unwrap();
+        }
+        ledger
+            .record_on("2018-11-13", Provider::DarkSky, Some("n1o2p3q4"), Some(40))
+            .unwrap();
+        ledger
+            .record_on("2018-11-13", Provider::Owm, Some("a1b2c3d4"), None)
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                Usage {
+                    provider: Provider::DarkSky,
+                    key: Some(String::from("…p3q4")),
+                    today: 40,
+                    month: 42,
+                    budget: None,
+                },
+                Usage {
+                    provider: Provider::Owm,
+                    key: Some(String::from("…c3d4")),
+                    today: 1,
+                    month: 1,
+                    budget: None,
+                },
+            ],
+            ledger.usage_on("2018-11-13")
+        );
+
+        // Last month's calls are gone, and the ledger is kept on disk.
+        let reopened = Ledger::open(ledger.path.clone(), &config);
+        assert_eq!(3, reopened.read().len());
+    }
+
+    #[test]
+    fn it_counts_calls_from_runs_at_the_same_time() {
+        let config = Config::default();
+        let path = ledger("concurrent", &config).path;
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                let ledger = Ledger::open(path.clone(), &config);
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        ledger
+                            .record_on("2018-11-13", Provider::DarkSky, Some("n1o2p3q4"), None)
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let ledger = Ledger::open(path, &config);
+        assert_eq!(40, ledger.usage_on("2018-11-13")[0].today);
+    }
+
+    #[test]
+    fn it_skips_dates_that_dont_make_sense() {
+        let config = Config::default();
+        let ledger = ledger("dates", &config);
+        fs::write(
+            &ledger.path,
+            r#"[{"provider":"darksky","key":null,"date":"11-13","calls":3,"reported":null}]"#,
+        )
+        .unwrap();
+
+        assert!(ledger.usage_on("2018-11-13").is_empty());
+        ledger
+            .record_on("2018-11-13", Provider::DarkSky, None, None)
+            .unwrap();
+        assert_eq!(1, ledger.read().len());
+    }
+
+    #[test]
+    fn it_stops_at_the_daily_budget() {
+        let config = Config {
+            darksky: Some(DarkSkyConfig {
+                key: String::from("n1o2p3q4"),
+                daily_budget: Some(2),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let ledger = ledger("budget", &config);
+
+        assert!(ledger.check(Provider::DarkSky, Some("n1o2p3q4")).is_ok());
+        ledger
+            .record(Provider::DarkSky, Some("n1o2p3q4"), None)
+            .unwrap();
+        assert!(ledger.check(Provider::DarkSky, Some("n1o2p3q4")).is_ok());
+        ledger
+            .record(Provider::DarkSky, Some("n1o2p3q4"), None)
+            .unwrap();
+
+        assert!(ledger.check(Provider::DarkSky, Some("n1o2p3q4")).is_err());
+        assert!(ledger.check(Provider::DarkSky, Some("other_key")).is_ok());
+        assert!(ledger.check(Provider::Owm, Some("n1o2p3q4")).is_ok());
+    }
+}
@@ -0,0 +1,313 @@
+//! Provider-neutral weather data.
+//!
+//! Every provider's models convert into a [`Report`], so consumers don't need to know which API
+//! answered. Ratios (humidity, cloud cover, precipitation probability) are always fractions
+//! between 0 and 1, bearings are in degrees, and times are Unix timestamps. Every other
+//! measurement is a [`Quantity`] that knows its unit, and [`Report::to`] puts them all in the
+//! same [`Units`].
+
+mod units;
+
+pub use self::units::*;
+use crate::weather_api::Provider;
+use chrono::FixedOffset;
+use chrono_tz::Tz;
+use serde_derive::Serialize;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub provider: Provider,
+    pub location: Location,
+    /// The units the provider answered in. After [`to`](Self::to), the ones every measurement is
+    /// in.
+    pub units: Units,
+    pub current: Option<Observation>,
+    pub hourly: Vec<HourlyPoint>,
+    pub daily: Vec<DailySummary>,
+    pub alerts: Vec<Alert>,
+    /// Seconds since the data was fetched, when it came from the cache.
+    pub age: Option<u64>,
+    /// The provider couldn't be reached, so this is old data from the cache.
+    pub stale: bool,
+    /// The providers that were tried first, but failed.
+    pub failed: Vec<Provider>,
+}
+
+impl Report {
+    pub fn new(provider: Provider, units: Units) -> Self {
+        Self {
+            provider,
+            location: Location::default(),
+            units,
+            current: None,
+            hourly: Vec::new(),
+            daily: Vec::new(),
+            alerts: Vec::new(),
+            age: None,
+            stale: false,
+            failed: Vec::new(),
+        }
+    }
+
+    /// The report with every measurement in `units`.
+    pub fn to(self, units: Units) -> Self {
+        Self {
+            units,
+            current: self.current.map(|current| current.to(units)),
+            hourly: self
+                .hourly
+                .into_iter()
+                .map(|point| point.to(units))
+                .collect(),
+            daily: self.daily.into_iter().map(|day| day.to(units)).collect(),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Location {
+    pub name: Option<String>,
+    pub country: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// IANA time zone name, e.g. `America/Winnipeg`.
+    pub timezone: Option<String>,
+    /// The offset from UTC, for providers that give one instead of a time zone name.
+    pub utc_offset: Option<FixedOffset>,
+}
+
+impl Location {
+    /// The time zone, when the provider named one that's known.
+    pub fn time_zone(&self) -> Option<Tz> {
+        self.timezone.as_ref()?.parse().ok()
+    }
+}
+
+/// Conditions at a single point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub time: i64,
+    pub summary: Option<String>,
+    pub condition: Condition,
+    pub temperature: Option<Temperature>,
+    pub apparent_temperature: Option<Temperature>,
+    pub dew_point: Option<Temperature>,
+    pub humidity: Option<f64>,
+    pub pressure: Option<Pressure>,
+    pub wind_speed: Option<Speed>,
+    pub wind_gust: Option<Speed>,
+    pub wind_bearing: Option<f64>,
+    pub cloud_cover: Option<f64>,
+    pub visibility: Option<Distance>,
+    pub uv_index: Option<f64>,
+    pub precip_intensity: Option<PrecipitationRate>,
+    pub precip_probability: Option<f64>,
+    pub sunrise: Option<i64>,
+    pub sunset: Option<i64>,
+}
+
+impl Observation {
+    pub fn new(time: i64) -> Self {
+        Self {
+            time,
+            summary: None,
+            condition: Condition::Unknown,
+            temperature: None,
+            apparent_temperature: None,
+            dew_point: None,
+            humidity: None,
+            pressure: None,
+            wind_speed: None,
+            wind_gust: None,
+            wind_bearing: None,
+            cloud_cover: None,
+            visibility: None,
+            uv_index: None,
+            precip_intensity: None,
+            precip_probability: None,
+            sunrise: None,
+            sunset: None,
+        }
+    }
+
+    fn to(self, units: Units) -> Self {
+        Self {
+            temperature: self.temperature.map(|t| t.to(units.temperature)),
+            apparent_temperature: self.apparent_temperature.map(|t| t.to(units.temperature)),
+            dew_point: self.dew_point.map(|t| t.to(units.temperature)),
+            pressure: self.pressure.map(|p| p.to(units.pressure)),
+            wind_speed: self.wind_speed.map(|s| s.to(units.speed)),
+            wind_gust: self.wind_gust.map(|s| s.to(units.speed)),
+            visibility: self.visibility.map(|d| d.to(units.distance)),
+            precip_intensity: self.precip_intensity.map(|p| p.to(units.precipitation)),
+            ..self
+        }
+    }
+}
+
+/// Forecast (or observed) conditions for a short period, usually one to three hours.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HourlyPoint {
+    pub time: i64,
+    pub summary: Option<String>,
+    pub condition: Condition,
+    pub temperature: Option<Temperature>,
+    pub apparent_temperature: Option<Temperature>,
+    pub humidity: Option<f64>,
+    pub pressure: Option<Pressure>,
+    pub wind_speed: Option<Speed>,
+    pub wind_bearing: Option<f64>,
+    pub cloud_cover: Option<f64>,
+    pub precip_intensity: Option<PrecipitationRate>,
+    pub precip_probability: Option<f64>,
+}
+
+impl HourlyPoint {
+    pub fn new(time: i64) -> Self {
+        Self {
+            time,
+            summary: None,
+            condition: Condition::Unknown,
+            temperature: None,
+            apparent_temperature: None,
+            humidity: None,
+            pressure: None,
+            wind_speed: None,
+            wind_bearing: None,
+            cloud_cover: None,
+            precip_intensity: None,
+            precip_probability: None,
+        }
+    }
+
+    fn to(self, units: Units) -> Self {
+        Self {
+            temperature: self.temperature.map(|t| t.to(units.temperature)),
+            apparent_temperature: self.apparent_temperature.map(|t| t.to(units.temperature)),
+            pressure: self.pressure.map(|p| p.to(units.pressure)),
+            wind_speed: self.wind_speed.map(|s| s.to(units.speed)),
+            precip_intensity: self.precip_intensity.map(|p| p.to(units.precipitation)),
+            ..self
+        }
+    }
+}
+
+/// Summary of a whole day. `time` is the start of the day in the location's time zone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailySummary {
+    pub time: i64,
+    pub summary: Option<String>,
+    pub condition: Condition,
+    pub temperature_high: Option<Temperature>,
+    pub temperature_low: Option<Temperature>,
+    pub apparent_temperature_high: Option<Temperature>,
+    pub apparent_temperature_low: Option<Temperature>,
+    pub humidity: Option<f64>,
+    pub pressure: Option<Pressure>,
+    pub wind_speed: Option<Speed>,
+    pub wind_bearing: Option<f64>,
+    pub precip_intensity_max: Option<PrecipitationRate>,
+    pub precip_probability: Option<f64>,
+    pub uv_index: Option<f64>,
+    pub sunrise: Option<i64>,
+    pub sunset: Option<i64>,
+}
+
+impl DailySummary {
+    pub fn new(time: i64) -> Self {
+        Self {
+            time,
+            summary: None,
+            condition: Condition::Unknown,
+            temperature_high: None,
+            temperature_low: None,
+            apparent_temperature_high: None,
+            apparent_temperature_low: None,
+            humidity: None,
+            pressure: None,
+            wind_speed: None,
+            wind_bearing: None,
+            precip_intensity_max: None,
+            precip_probability: None,
+            uv_index: None,
+            sunrise: None,
+            sunset: None,
+        }
+    }
+
+    fn to(self, units: Units) -> Self {
+        Self {
+            temperature_high: self.temperature_high.map(|t| t.to(units.temperature)),
+            temperature_low: self.temperature_low.map(|t| t.to(units.temperature)),
+            apparent_temperature_high: self
+                .apparent_temperature_high
+                .map(|t| t.to(units.temperature)),
+            apparent_temperature_low: self
+                .apparent_temperature_low
+                .map(|t| t.to(units.temperature)),
+            pressure: self.pressure.map(|p| p.to(units.pressure)),
+            wind_speed: self.wind_speed.map(|s| s.to(units.speed)),
+            precip_intensity_max: self.precip_intensity_max.map(|p| p.to(units.precipitation)),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub title: String,
+    pub description: String,
+    pub severity: Option<Severity>,
+    pub regions: Vec<String>,
+    pub issued: i64,
+    pub expires: Option<i64>,
+    pub uri: Option<String>,
+}
+
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab_case")]
+pub enum Severity {
+    Advisory,
+    Watch,
+    Warning,
+}
+
+/// The general state of the sky, close to DarkSky's icon set.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab_case")]
+pub enum Condition {
+    ClearDay,
+    ClearNight,
+    PartlyCloudyDay,
+    PartlyCloudyNight,
+    Cloudy,
+    Rain,
+    Snow,
+    Sleet,
+    Wind,
+    Fog,
+    Thunderstorm,
+    Unknown,
+}
+
+impl Condition {
+    /// A single glyph representing the condition, for places with little room like status bars.
+    pub fn icon(self) -> &'static str {
+        match self {
+            Condition::ClearDay => "☀",
+            Condition::ClearNight => "☾",
+            Condition::PartlyCloudyDay | Condition::PartlyCloudyNight => "⛅",
+            Condition::Cloudy => "☁",
+            Condition::Rain => "☂",
+            Condition::Snow => "❄",
+            Condition::Sleet => "☔",
+            Condition::Wind => "≋",
+            Condition::Fog => "≡",
+            Condition::Thunderstorm => "⚡",
+            Condition::Unknown => "?",
+        }
+    }
+}
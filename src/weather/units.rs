@@ -0,0 +1,338 @@
+use crate::config::{ArgEnum, DarkSkyUnit, GenericWeatherUnit, OwmUnit};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+/// A unit some kind of measurement can be in.
+pub trait Unit: fmt::Debug + Copy + PartialEq {
+    fn symbol(self) -> &'static str;
+
+    /// `value`, which is in this unit, in `unit` instead.
+    fn convert(self, value: f64, unit: Self) -> f64;
+
+    /// `value` in this unit, when there is one.
+    fn of(self, value: Option<f64>) -> Option<Quantity<Self>> {
+        value.map(|value| Quantity::new(value, self))
+    }
+}
+
+/// A measurement, and the unit it's in.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quantity<U> {
+    pub value: f64,
+    pub unit: U,
+}
+
+pub type Temperature = Quantity<TemperatureUnit>;
+pub type Speed = Quantity<SpeedUnit>;
+pub type Pressure = Quantity<PressureUnit>;
+pub type Distance = Quantity<DistanceUnit>;
+pub type PrecipitationRate = Quantity<PrecipitationUnit>;
+
+impl<U: Unit> Quantity<U> {
+    pub fn new(value: f64, unit: U) -> Self {
+        Self { value, unit }
+    }
+
+    /// The same measurement in `unit`.
+    pub fn to(self, unit: U) -> Self {
+        Self::new(self.value_in(unit), unit)
+    }
+
+    pub fn value_in(self, unit: U) -> f64 {
+        if unit == self.unit {
+            self.value
+        } else {
+            self.unit.convert(self.value, unit)
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumString, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl ArgEnum for TemperatureUnit {
+    const VARIANTS: &'static [&'static str] = &["celsius", "fahrenheit", "kelvin"];
+}
+
+impl Unit for TemperatureUnit {
+    fn symbol(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+            TemperatureUnit::Kelvin => "K",
+        }
+    }
+
+    fn convert(self, value: f64, unit: TemperatureUnit) -> f64 {
+        let celsius = match self {
+            TemperatureUnit::Celsius => value,
+            TemperatureUnit::Fahrenheit => (value - 32.) / 1.8,
+            TemperatureUnit::Kelvin => value - 273.15,
+        };
+
+        match unit {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 1.8 + 32.,
+            TemperatureUnit::Kelvin => celsius + 273.15,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumString, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum SpeedUnit {
+    MetresPerSecond,
+    KilometresPerHour,
+    MilesPerHour,
+}
+
+impl ArgEnum for SpeedUnit {
+    const VARIANTS: &'static [&'static str] =
+        &["metres_per_second", "kilometres_per_hour", "miles_per_hour"];
+}
+
+impl Unit for SpeedUnit {
+    fn symbol(self) -> &'static str {
+        match self {
+            SpeedUnit::MetresPerSecond => "m/s",
+            SpeedUnit::KilometresPerHour => "km/h",
+            SpeedUnit::MilesPerHour => "mph",
+        }
+    }
+
+    fn convert(self, value: f64, unit: SpeedUnit) -> f64 {
+        let metres_per_hour = |unit| match unit {
+            SpeedUnit::MetresPerSecond => 3600.,
+            SpeedUnit::KilometresPerHour => 1000.,
+            SpeedUnit::MilesPerHour => 1609.344,
+        };
+
+        value * metres_per_hour(self) / metres_per_hour(unit)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumString, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum PressureUnit {
+    Hectopascals,
+    Kilopascals,
+    InchesOfMercury,
+}
+
+impl ArgEnum for PressureUnit {
+    const VARIANTS: &'static [&'static str] = &["hectopascals", "kilopascals", "inches_of_mercury"];
+}
+
+impl Unit for PressureUnit {
+    fn symbol(self) -> &'static str {
+        match self {
+            PressureUnit::Hectopascals => "hPa",
+            PressureUnit::Kilopascals => "kPa",
+            PressureUnit::InchesOfMercury => "inHg",
+        }
+    }
+
+    fn convert(self, value: f64, unit: PressureUnit) -> f64 {
+        let hectopascals = |unit| match unit {
+            PressureUnit::Hectopascals => 1.,
+            PressureUnit::Kilopascals => 10.,
+            PressureUnit::InchesOfMercury => 33.863_89,
+        };
+
+        value * hectopascals(self) / hectopascals(unit)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumString, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum DistanceUnit {
+    Kilometres,
+    Miles,
+}
+
+impl ArgEnum for DistanceUnit {
+    const VARIANTS: &'static [&'static str] = &["kilometres", "miles"];
+}
+
+impl Unit for DistanceUnit {
+    fn symbol(self) -> &'static str {
+        match self {
+            DistanceUnit::Kilometres => "km",
+            DistanceUnit::Miles => "mi",
+        }
+    }
+
+    fn convert(self, value: f64, unit: DistanceUnit) -> f64 {
+        let kilometres = |unit| match unit {
+            DistanceUnit::Kilometres => 1.,
+            DistanceUnit::Miles => 1.609_344,
+        };
+
+        value * kilometres(self) / kilometres(unit)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumString, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum PrecipitationUnit {
+    MillimetresPerHour,
+    InchesPerHour,
+}
+
+impl ArgEnum for PrecipitationUnit {
+    const VARIANTS: &'static [&'static str] = &["millimetres_per_hour", "inches_per_hour"];
+}
+
+impl Unit for PrecipitationUnit {
+    fn symbol(self) -> &'static str {
+        match self {
+            PrecipitationUnit::MillimetresPerHour => "mm/h",
+            PrecipitationUnit::InchesPerHour => "in/h",
+        }
+    }
+
+    fn convert(self, value: f64, unit: PrecipitationUnit) -> f64 {
+        let millimetres_per_hour = |unit| match unit {
+            PrecipitationUnit::MillimetresPerHour => 1.,
+            PrecipitationUnit::InchesPerHour => 25.4,
+        };
+
+        value * millimetres_per_hour(self) / millimetres_per_hour(unit)
+    }
+}
+
+/// A unit for each kind of measurement, like the ones a [`Report`](super::Report) came in or is
+/// shown in.
+///
+/// Providers don't agree on what a "unit system" means (DarkSky's `ca` mixes Celsius with km/h,
+/// OWM reports visibility in metres no matter what), so each kind of measurement gets its own unit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Units {
+    pub temperature: TemperatureUnit,
+    pub speed: SpeedUnit,
+    pub pressure: PressureUnit,
+    pub distance: DistanceUnit,
+    pub precipitation: PrecipitationUnit,
+}
+
+impl From<Option<OwmUnit>> for Units {
+    fn from(unit: Option<OwmUnit>) -> Self {
+        // OWM always reports visibility in metres (converted to kilometres when normalizing) and
+        // precipitation in millimetres; only temperature and wind speed follow the unit.
+        let (temperature, speed) = match unit {
+            None => (TemperatureUnit::Kelvin, SpeedUnit::MetresPerSecond),
+            Some(OwmUnit::Metric) => (TemperatureUnit::Celsius, SpeedUnit::MetresPerSecond),
+            Some(OwmUnit::Imperial) => (TemperatureUnit::Fahrenheit, SpeedUnit::MilesPerHour),
+        };
+
+        Self {
+            temperature,
+            speed,
+            pressure: PressureUnit::Hectopascals,
+            distance: DistanceUnit::Kilometres,
+            precipitation: PrecipitationUnit::MillimetresPerHour,
+        }
+    }
+}
+
+impl From<GenericWeatherUnit> for Units {
+    fn from(unit: GenericWeatherUnit) -> Self {
+        match unit {
+            GenericWeatherUnit::Metric => Self {
+                temperature: TemperatureUnit::Celsius,
+                speed: SpeedUnit::MetresPerSecond,
+                pressure: PressureUnit::Hectopascals,
+                distance: DistanceUnit::Kilometres,
+                precipitation: PrecipitationUnit::MillimetresPerHour,
+            },
+            GenericWeatherUnit::Imperial => Self {
+                temperature: TemperatureUnit::Fahrenheit,
+                speed: SpeedUnit::MilesPerHour,
+                pressure: PressureUnit::Hectopascals,
+                distance: DistanceUnit::Miles,
+                precipitation: PrecipitationUnit::InchesPerHour,
+            },
+        }
+    }
+}
+
+impl From<DarkSkyUnit> for Units {
+    fn from(unit: DarkSkyUnit) -> Self {
+        match unit {
+            DarkSkyUnit::Si => Self {
+                temperature: TemperatureUnit::Celsius,
+                speed: SpeedUnit::MetresPerSecond,
+                pressure: PressureUnit::Hectopascals,
+                distance: DistanceUnit::Kilometres,
+                precipitation: PrecipitationUnit::MillimetresPerHour,
+            },
+            DarkSkyUnit::Ca => Self {
+                speed: SpeedUnit::KilometresPerHour,
+                ..Self::from(DarkSkyUnit::Si)
+            },
+            DarkSkyUnit::Uk2 => Self {
+                speed: SpeedUnit::MilesPerHour,
+                distance: DistanceUnit::Miles,
+                ..Self::from(DarkSkyUnit::Si)
+            },
+            // DarkSky only answers with "auto" when asked for it and the flags block is excluded,
+            // in which case the location decides. US units are the API's default.
+            DarkSkyUnit::Us | DarkSkyUnit::Auto => Self {
+                temperature: TemperatureUnit::Fahrenheit,
+                speed: SpeedUnit::MilesPerHour,
+                pressure: PressureUnit::Hectopascals,
+                distance: DistanceUnit::Miles,
+                precipitation: PrecipitationUnit::InchesPerHour,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: f64, actual: f64) {
+        assert!(
+            (expected - actual).abs() < 1e-9,
+            "{} isn't {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn it_converts_between_units() {
+        let freezing = Temperature::new(32., TemperatureUnit::Fahrenheit);
+        assert_close(0., freezing.value_in(TemperatureUnit::Celsius));
+        assert_close(273.15, freezing.value_in(TemperatureUnit::Kelvin));
+
+        let speed = Speed::new(10., SpeedUnit::MetresPerSecond);
+        assert_close(36., speed.value_in(SpeedUnit::KilometresPerHour));
+        assert_close(22.369_362_920_544, speed.value_in(SpeedUnit::MilesPerHour));
+
+        let pressure = Pressure::new(101.325, PressureUnit::Kilopascals);
+        assert_close(1013.25, pressure.value_in(PressureUnit::Hectopascals));
+        assert_close(
+            29.92,
+            (pressure.value_in(PressureUnit::InchesOfMercury) * 100.).round() / 100.,
+        );
+
+        let distance = Distance::new(1., DistanceUnit::Miles).to(DistanceUnit::Kilometres);
+        assert_eq!(DistanceUnit::Kilometres, distance.unit);
+        assert_close(1.609_344, distance.value);
+
+        let rate = PrecipitationRate::new(1., PrecipitationUnit::InchesPerHour);
+        assert_close(25.4, rate.value_in(PrecipitationUnit::MillimetresPerHour));
+    }
+}
@@ -1,32 +1,79 @@
 use super::Config;
+use crate::cache::Endpoint;
+use crate::weather::Report;
 use clap::ArgMatches;
 use failure::Error;
+use reqwest::header::HeaderMap;
 use reqwest::Client;
 use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
 use std::fmt;
+use std::panic;
+use std::thread::ScopedJoinHandle;
 use url::Url;
 
 pub mod darksky;
+pub mod eccc;
+mod fetcher;
+pub mod metno;
+pub mod nws;
+pub mod openmeteo;
 pub mod owm;
+pub mod pirateweather;
 
-fn fetch_json<T, E>(client: &Client, url: Url) -> Result<T, failure::Error>
-where
-    T: DeserializeOwned,
-    E: DeserializeOwned + failure::Fail,
-{
-    client
-        .get(url)
-        .send()
-        .map_err(Error::from)
-        .and_then(|mut res| match res.status() {
-            status if status.is_success() => Ok(res.json::<T>()?),
-            status if status.is_client_error() => Err(res.json::<E>().map(Error::from)?),
-            _ => Err(Error::from(res.error_for_status().unwrap_err())),
-        })
+pub use self::fetcher::{Fetched, Fetcher, Format};
+
+#[derive(
+    Debug, Display, EnumString, EnumIter, Copy, Clone, PartialEq, Eq, Deserialize, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    #[strum(serialize = "darksky")]
+    DarkSky,
+    #[strum(serialize = "eccc")]
+    Eccc,
+    #[strum(serialize = "metno")]
+    MetNo,
+    #[strum(serialize = "nws")]
+    Nws,
+    #[strum(serialize = "openmeteo")]
+    OpenMeteo,
+    #[strum(serialize = "owm")]
+    Owm,
+    #[strum(serialize = "owm-onecall")]
+    #[serde(rename = "owm-onecall")]
+    OwmOneCall,
+    #[strum(serialize = "pirateweather")]
+    PirateWeather,
+}
+
+impl Provider {
+    /// The provider's name, as it should be shown to people.
+    pub fn name(self) -> &'static str {
+        match self {
+            Provider::DarkSky => "Dark Sky",
+            Provider::Eccc => "Environment Canada",
+            Provider::MetNo => "MET Norway",
+            Provider::Nws => "National Weather Service",
+            Provider::OpenMeteo => "Open-Meteo",
+            Provider::Owm => "OpenWeatherMap",
+            Provider::OwmOneCall => "OpenWeatherMap One Call",
+            Provider::PirateWeather => "Pirate Weather",
+        }
+    }
+
+    /// Whether responses are cached until their `Expires` header, even past the `[cache]` TTL.
+    /// MET Norway's terms ask not to be fetched again before then; the others' headers are left
+    /// alone, so they don't shorten the TTLs.
+    pub fn follows_expires(self) -> bool {
+        self == Provider::MetNo
+    }
 }
 
 pub trait WeatherApi<'a> {
     const BASE_URL: &'static str;
+    /// Which provider this is, for the usage ledger.
+    const PROVIDER: Provider;
     type Current: std::fmt::Debug + DeserializeOwned;
     type ApiError: std::fmt::Debug + failure::Fail + DeserializeOwned;
 
@@ -34,16 +81,68 @@ pub trait WeatherApi<'a> {
     fn url(&self) -> Url;
     fn current_url(&self) -> Url;
 
+    /// Convert the current conditions into a provider-neutral report.
+    fn report(&self, current: Self::Current) -> Report;
+
+    /// The API key, if the provider needs one. It's kept out of cache keys and logs.
+    fn api_key(&self) -> Option<&str> {
+        None
+    }
+
+    /// Headers to send with every request, for providers that want more than a key.
+    fn headers(&self) -> HeaderMap {
+        HeaderMap::new()
+    }
+
     fn current(&self, client: &Client) -> Result<Self::Current, Error> {
-        fetch_json::<Self::Current, Self::ApiError>(client, self.current_url())
+        self.fetch_current(&Fetcher::new(client, None))
+            .map(|fetched| fetched.value)
+    }
+
+    fn fetch_current(&self, fetcher: &Fetcher) -> Result<Fetched<Self::Current>, Error> {
+        fetcher.fetch::<Self::Current, Self::ApiError>(
+            Self::PROVIDER,
+            Endpoint::Current,
+            self.current_url(),
+            self.api_key(),
+            self.headers(),
+        )
+    }
+
+    /// Fetch the current conditions as a provider-neutral report.
+    fn current_report(&self, fetcher: &Fetcher) -> Result<Report, Error> {
+        self.fetch_current(fetcher)
+            .map(|fetched| fetched.into_report(|current| self.report(current)))
     }
 }
 
 pub trait HistoricalApi<'a>: WeatherApi<'a> {
+    type Historical: fmt::Debug + DeserializeOwned;
+
     fn historical_url(&self, time: i64) -> Url;
 
-    fn historical(&self, client: &Client, time: i64) -> Result<serde_json::Value, Error> {
-        fetch_json::<serde_json::Value, Self::ApiError>(client, self.historical_url(time))
+    /// Convert the historical data into a provider-neutral report.
+    ///
+    /// `time` is the time that was asked for, for providers that answer with a whole range.
+    fn historical_report(&self, historical: Self::Historical, time: i64) -> Report;
+
+    fn historical(&self, client: &Client, time: i64) -> Result<Self::Historical, Error> {
+        self.fetch_historical(&Fetcher::new(client, None), time)
+            .map(|fetched| fetched.value)
+    }
+
+    fn fetch_historical(
+        &self,
+        fetcher: &Fetcher,
+        time: i64,
+    ) -> Result<Fetched<Self::Historical>, Error> {
+        fetcher.fetch::<Self::Historical, Self::ApiError>(
+            Self::PROVIDER,
+            Endpoint::Historical,
+            self.historical_url(time),
+            self.api_key(),
+            self.headers(),
+        )
     }
 }
 
@@ -52,7 +151,63 @@ pub trait ForecastApi<'a>: WeatherApi<'a> {
 
     fn forecast_url(&self) -> Url;
 
+    /// Convert the forecast into a provider-neutral report.
+    fn forecast_report(&self, forecast: Self::Forecast) -> Report;
+
     fn forecast(&self, client: &Client) -> Result<Self::Forecast, Error> {
-        fetch_json::<Self::Forecast, Self::ApiError>(client, self.forecast_url())
+        self.fetch_forecast(&Fetcher::new(client, None))
+            .map(|fetched| fetched.value)
+    }
+
+    fn fetch_forecast(&self, fetcher: &Fetcher) -> Result<Fetched<Self::Forecast>, Error> {
+        fetcher.fetch::<Self::Forecast, Self::ApiError>(
+            Self::PROVIDER,
+            Endpoint::Forecast,
+            self.forecast_url(),
+            self.api_key(),
+            self.headers(),
+        )
+    }
+}
+
+/// `url`, or its path under `base_url` when that's set, so a provider's API can be reached
+/// through a proxy or a compatible server.
+///
+/// `base_url` has to have passed [`check_base_url`], which happens when it's read.
+fn rebase(url: &str, base_url: Option<&str>) -> Url {
+    let url = Url::parse(url).unwrap();
+    let base_url = match base_url {
+        Some(base_url) => base_url,
+        None => return url,
+    };
+
+    let mut rebased = Url::parse(base_url).expect("base_url was checked");
+    rebased
+        .path_segments_mut()
+        .expect("base_url was checked")
+        .pop_if_empty()
+        .extend(url.path_segments().into_iter().flatten());
+
+    rebased
+}
+
+/// Whether the API's paths can be put under `base_url`, which needs an absolute URL like
+/// `http://localhost:8080` (`localhost:8080` is read as a URL with the `localhost` scheme).
+pub(crate) fn check_base_url(base_url: &str) -> Result<(), String> {
+    let url = Url::parse(base_url).map_err(|e| format!("invalid base_url {}: {}", base_url, e))?;
+    if url.cannot_be_a_base() {
+        return Err(format!(
+            "base_url {} can't have a path; use something like http://localhost:8080",
+            base_url
+        ));
     }
+
+    Ok(())
+}
+
+/// Wait for a thread that's fetching something, passing its panic on.
+pub(crate) fn join<T>(handle: ScopedJoinHandle<T>) -> T {
+    handle
+        .join()
+        .unwrap_or_else(|panic| panic::resume_unwind(panic))
 }
@@ -1,22 +1,26 @@
 use super::Config;
-use super::{HistoricalApi, WeatherApi};
+use super::{rebase, HistoricalApi, Provider, WeatherApi};
 use crate::config::{DarkSkyUnit, GenericWeatherUnit};
+use crate::weather::Report;
 use clap::ArgMatches;
 use url::Url;
 
 mod models;
 
-pub use self::models::{DarkSkyError, Forecast};
+pub use self::models::{DarkSkyError, DataPoint, Forecast};
 
 #[derive(Debug, PartialEq)]
 pub struct DarkSky<'a> {
     pub key: &'a str,
     pub coordinates: (f64, f64),
     pub unit: Option<DarkSkyUnit>,
+    /// Replaces `https://api.darksky.net`.
+    pub base_url: Option<&'a str>,
 }
 
 impl<'a, 'c: 'a> WeatherApi<'c> for DarkSky<'a> {
     const BASE_URL: &'static str = "https://api.darksky.net/forecast";
+    const PROVIDER: Provider = Provider::DarkSky;
     type Current = Forecast;
     type ApiError = DarkSkyError;
 
@@ -43,61 +47,103 @@ impl<'a, 'c: 'a> WeatherApi<'c> for DarkSky<'a> {
             .or(darksky.unit)
             .or_else(|| config.unit.map(DarkSkyUnit::from));
 
+        let base_url = darksky_matches
+            .and_then(|dm| dm.value_of("base-url"))
+            .or(darksky.base_url.as_deref());
+
         Self {
             key,
             coordinates: (latitude, longitude),
             unit,
+            base_url,
         }
     }
 
     fn url(&self) -> Url {
-        let mut url = Url::parse(&format!(
-            "{base}/{key}/{lat},{lon}",
-            base = Self::BASE_URL,
-            key = self.key,
-            lat = self.coordinates.0.to_string(),
-            lon = self.coordinates.1.to_string()
-        ))
-        .unwrap();
-
-        if let Some(unit) = self.unit {
-            url.query_pairs_mut()
-                .append_pair("units", &unit.to_string())
-                .finish();
-        }
-
-        url
+        url(
+            rebase(Self::BASE_URL, self.base_url),
+            self.key,
+            self.coordinates,
+            self.unit,
+        )
     }
 
     fn current_url(&self) -> Url {
-        let mut url = self.url();
-        url.query_pairs_mut()
-            .append_pair("exclude", "minutely,hourly,daily,alerts,flags")
-            .finish();
+        current_url(self.url())
+    }
+
+    fn api_key(&self) -> Option<&str> {
+        Some(self.key)
+    }
 
-        url
+    fn report(&self, current: Forecast) -> Report {
+        current.into_report(Provider::DarkSky, self.unit)
     }
 }
 
 impl<'a> HistoricalApi<'a> for DarkSky<'a> {
+    type Historical = Forecast;
+
     fn historical_url(&self, time: i64) -> Url {
-        let mut url = self.url();
+        historical_url(self.url(), self.coordinates, time)
+    }
+
+    fn historical_report(&self, historical: Forecast, _time: i64) -> Report {
+        historical.into_report(Provider::DarkSky, self.unit)
+    }
+}
 
-        url.path_segments_mut().unwrap().pop().push(&format!(
-            "{lat},{lon},{time}",
-            lat = self.coordinates.0,
-            lon = self.coordinates.1,
-            time = time
-        ));
+/// The forecast URL for an API that speaks DarkSky's format, under `base`.
+pub(super) fn url(
+    mut base: Url,
+    key: &str,
+    coordinates: (f64, f64),
+    unit: Option<DarkSkyUnit>,
+) -> Url {
+    base.path_segments_mut().unwrap().push(key).push(&format!(
+        "{lat},{lon}",
+        lat = coordinates.0,
+        lon = coordinates.1
+    ));
+    let mut url = base;
 
-        url
+    if let Some(unit) = unit {
+        url.query_pairs_mut()
+            .append_pair("units", &unit.to_string())
+            .finish();
     }
+
+    url
+}
+
+/// Just the current conditions from a forecast URL.
+pub(super) fn current_url(mut url: Url) -> Url {
+    // Keep the flags so the response says which units it's in, and today's summary for the
+    // sunrise and sunset.
+    url.query_pairs_mut()
+        .append_pair("exclude", "minutely,hourly,alerts")
+        .finish();
+
+    url
+}
+
+/// A forecast URL turned into a Time Machine request for `time`.
+pub(super) fn historical_url(mut url: Url, coordinates: (f64, f64), time: i64) -> Url {
+    url.path_segments_mut().unwrap().pop().push(&format!(
+        "{lat},{lon},{time}",
+        lat = coordinates.0,
+        lon = coordinates.1,
+        time = time
+    ));
+
+    url
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::{Config, DarkSkyConfig};
+    use crate::weather::{Condition, SpeedUnit};
 
     #[test]
     fn it_creates_new_darksky_with_coordinates() {
@@ -120,6 +166,7 @@ mod tests {
                 key: "my_key",
                 coordinates: (12.345, -54.321),
                 unit: None,
+                base_url: None,
             },
             api
         );
@@ -133,6 +180,7 @@ mod tests {
             darksky: Some(DarkSkyConfig {
                 key: String::from("my_key"),
                 unit: Some(DarkSkyUnit::Uk2),
+                ..Default::default()
             }),
             ..Default::default()
         };
@@ -150,18 +198,46 @@ mod tests {
                 key: "my_key",
                 coordinates: (12.345, -54.321),
                 unit: Some(DarkSkyUnit::Uk2),
+                base_url: None,
             },
             api
         );
         assert_eq!(expected_url, api.url());
     }
 
+    #[test]
+    fn it_sends_requests_to_the_base_url() {
+        let config = Config {
+            coordinates: Some((12.345, -54.321)),
+            darksky: Some(DarkSkyConfig {
+                key: String::from("my_key"),
+                base_url: Some(String::from("https://example.com/darksky/")),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let matches = clap::App::new("name").get_matches_from(vec!["name"]);
+        let api = DarkSky::new(&config, &matches);
+
+        assert_eq!(
+            Url::parse("https://example.com/darksky/forecast/my_key/12.345,-54.321").unwrap(),
+            api.url()
+        );
+        assert_eq!(
+            Url::parse("https://example.com/darksky/forecast/my_key/12.345,-54.321,1542143061")
+                .unwrap(),
+            api.historical_url(1_542_143_061)
+        );
+    }
+
     #[test]
     fn it_gets_darksky_historical_weather() {
         let api = DarkSky {
             key: "my_key",
             coordinates: (12.345, -54.321),
             unit: None,
+            base_url: None,
         };
         #[allow(clippy::unreadable_literal)]
         let time = 1542143061;
@@ -175,4 +251,49 @@ mod tests {
 
         assert_eq!(expected_url, actual_url);
     }
+
+    #[test]
+    fn it_normalizes_a_forecast() {
+        let forecast: Forecast = serde_json::from_str(
+            r#"{
+                "latitude": 12.345,
+                "longitude": -54.321,
+                "timezone": "America/Winnipeg",
+                "currently": {
+                    "time": 1542143061,
+                    "summary": "Partly Cloudy",
+                    "icon": "partly-cloudy-day",
+                    "temperature": 1.5,
+                    "humidity": 0.8,
+                    "windBearing": 270
+                },
+                "daily": {
+                    "data": [
+                        { "time": 1542088800, "sunriseTime": 1542116053, "sunsetTime": 1542150213 }
+                    ]
+                },
+                "flags": { "sources": [], "units": "ca" }
+            }"#,
+        )
+        .unwrap();
+
+        let api = DarkSky {
+            key: "my_key",
+            coordinates: (12.345, -54.321),
+            unit: None,
+            base_url: None,
+        };
+        let report = api.report(forecast);
+        let current = report.current.unwrap();
+
+        assert_eq!(
+            Some(String::from("America/Winnipeg")),
+            report.location.timezone
+        );
+        assert_eq!(SpeedUnit::KilometresPerHour, report.units.speed);
+        assert_eq!(Condition::PartlyCloudyDay, current.condition);
+        assert_eq!(Some(270.), current.wind_bearing);
+        assert_eq!(Some(1_542_116_053), current.sunrise);
+        assert_eq!(1, report.daily.len());
+    }
 }
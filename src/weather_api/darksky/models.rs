@@ -1,6 +1,10 @@
 use super::DarkSkyUnit;
+use crate::weather::{
+    self, Condition, DailySummary, HourlyPoint, Location, Observation, Report, Unit, Units,
+};
+use crate::weather_api::Provider;
 use failure::Fail;
-use serde_derive::Deserialize;
+use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 
 #[derive(Fail, Debug, Deserialize)]
@@ -21,9 +25,11 @@ pub struct Forecast {
     latitude: f64,
     longitude: f64,
     timezone: String,
-    // deprecated
-    offset: Option<i32>,
+    // deprecated; in hours, which aren't always whole
+    #[allow(dead_code)]
+    offset: Option<f64>,
     currently: Option<DataPoint>,
+    #[allow(dead_code)]
     minutely: Option<DataBlock>,
     hourly: Option<DataBlock>,
     daily: Option<DataBlock>,
@@ -31,8 +37,9 @@ pub struct Forecast {
     flags: Option<Flags>,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// A data point, which is serialized with the field names as they are here (for CSV headers).
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
 pub struct DataPoint {
     apparent_temperature: Option<f64>,
     apparent_temperature_high: Option<f64>,
@@ -90,15 +97,15 @@ pub struct DataPoint {
 }
 
 #[derive(Debug, Deserialize)]
-#[serde(deny_unknown_fields)]
 pub struct DataBlock {
     data: Vec<DataPoint>,
+    #[allow(dead_code)]
     summary: Option<String>,
+    #[allow(dead_code)]
     icon: Option<Icon>,
 }
 
 #[derive(Debug, Deserialize)]
-#[serde(deny_unknown_fields)]
 pub struct Alerts {
     description: String,
     expires: i64,
@@ -111,17 +118,20 @@ pub struct Alerts {
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "kebab-case")]
-#[serde(deny_unknown_fields)]
 pub struct Flags {
+    #[allow(dead_code)]
     darksky_unavailable: Option<String>,
+    #[allow(dead_code)]
     nearest_station: Option<f64>,
+    #[allow(dead_code)]
     sources: Vec<String>,
     // undocumented
+    #[allow(dead_code)]
     meteoalarm_license: Option<String>,
     units: DarkSkyUnit,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 #[serde(deny_unknown_fields)]
 pub enum Icon {
@@ -135,18 +145,21 @@ pub enum Icon {
     Cloudy,
     PartlyCloudyDay,
     PartlyCloudyNight,
-    #[doc(hide)]
+    #[doc(hidden)]
     #[serde(other)]
     Unknown,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 #[serde(deny_unknown_fields)]
 pub enum PrecipitationType {
     Rain,
     Snow,
     Sleet,
+    #[doc(hidden)]
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Deserialize)]
@@ -156,4 +169,184 @@ pub enum Severity {
     Advisory,
     Watch,
     Warning,
+    #[doc(hidden)]
+    #[serde(other)]
+    Unknown,
+}
+
+impl Forecast {
+    /// Convert into a provider-neutral report.
+    ///
+    /// The units come from the response's flags when they're included, since `auto` lets DarkSky
+    /// pick them. Otherwise the requested unit is used, falling back to DarkSky's default.
+    ///
+    /// `provider` is whoever answered, since other APIs speak the same format.
+    pub fn into_report(self, provider: Provider, unit: Option<DarkSkyUnit>) -> Report {
+        let units = Units::from(self.unit(unit));
+        let mut report = Report::new(provider, units);
+
+        report.location = Location {
+            latitude: Some(self.latitude),
+            longitude: Some(self.longitude),
+            timezone: Some(self.timezone),
+            ..Default::default()
+        };
+
+        report.daily = self
+            .daily
+            .map(|daily| daily.data)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|point| point.into_daily(units))
+            .collect();
+
+        report.current = self.currently.map(|currently| {
+            let mut observation = currently.into_observation(units);
+            if let Some(today) = report.daily.first() {
+                observation.sunrise = observation.sunrise.or(today.sunrise);
+                observation.sunset = observation.sunset.or(today.sunset);
+            }
+            observation
+        });
+
+        report.hourly = self
+            .hourly
+            .map(|hourly| hourly.data)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|point| point.into_hourly(units))
+            .collect();
+
+        report.alerts = self
+            .alerts
+            .unwrap_or_default()
+            .into_iter()
+            .map(Alerts::into_alert)
+            .collect();
+
+        report
+    }
+
+    /// The unit system the data is in, given the one that was asked for.
+    pub fn unit(&self, requested: Option<DarkSkyUnit>) -> DarkSkyUnit {
+        self.flags
+            .as_ref()
+            .map(|flags| flags.units)
+            .or(requested)
+            .unwrap_or(DarkSkyUnit::Us)
+    }
+
+    /// The hourly and daily data points, as they came.
+    pub fn into_data_points(self) -> (Vec<DataPoint>, Vec<DataPoint>) {
+        (
+            self.hourly.map(|hourly| hourly.data).unwrap_or_default(),
+            self.daily.map(|daily| daily.data).unwrap_or_default(),
+        )
+    }
+}
+
+impl DataPoint {
+    fn into_observation(self, units: Units) -> Observation {
+        Observation {
+            summary: self.summary,
+            condition: condition(&self.icon),
+            temperature: units.temperature.of(self.temperature),
+            apparent_temperature: units.temperature.of(self.apparent_temperature),
+            dew_point: units.temperature.of(self.dew_point),
+            humidity: self.humidity,
+            pressure: units.pressure.of(self.pressure),
+            wind_speed: units.speed.of(self.wind_speed),
+            wind_gust: units.speed.of(self.wind_gust),
+            wind_bearing: self.wind_bearing.map(f64::from),
+            cloud_cover: self.cloud_cover,
+            visibility: units.distance.of(self.visibility),
+            uv_index: self.uv_index.map(f64::from),
+            precip_intensity: units.precipitation.of(self.precip_intensity),
+            precip_probability: self.precip_probability,
+            sunrise: self.sunrise_time,
+            sunset: self.sunset_time.map(|time| time as i64),
+            ..Observation::new(self.time)
+        }
+    }
+
+    fn into_hourly(self, units: Units) -> HourlyPoint {
+        HourlyPoint {
+            summary: self.summary,
+            condition: condition(&self.icon),
+            temperature: units.temperature.of(self.temperature),
+            apparent_temperature: units.temperature.of(self.apparent_temperature),
+            humidity: self.humidity,
+            pressure: units.pressure.of(self.pressure),
+            wind_speed: units.speed.of(self.wind_speed),
+            wind_bearing: self.wind_bearing.map(f64::from),
+            cloud_cover: self.cloud_cover,
+            precip_intensity: units.precipitation.of(self.precip_intensity),
+            precip_probability: self.precip_probability,
+            ..HourlyPoint::new(self.time)
+        }
+    }
+
+    fn into_daily(self, units: Units) -> DailySummary {
+        DailySummary {
+            summary: self.summary,
+            condition: condition(&self.icon),
+            temperature_high: units
+                .temperature
+                .of(self.temperature_high.or(self.temperature_max)),
+            temperature_low: units
+                .temperature
+                .of(self.temperature_low.or(self.temperature_min)),
+            apparent_temperature_high: units.temperature.of(self
+                .apparent_temperature_high
+                .or(self.apparent_temperature_max)),
+            apparent_temperature_low: units.temperature.of(self
+                .apparent_temperature_low
+                .or(self.apparent_temperature_min)),
+            humidity: self.humidity,
+            pressure: units.pressure.of(self.pressure),
+            wind_speed: units.speed.of(self.wind_speed),
+            wind_bearing: self.wind_bearing.map(f64::from),
+            precip_intensity_max: units.precipitation.of(self.precip_intensity_max),
+            precip_probability: self.precip_probability,
+            uv_index: self.uv_index.map(f64::from),
+            sunrise: self.sunrise_time,
+            sunset: self.sunset_time.map(|time| time as i64),
+            ..DailySummary::new(self.time)
+        }
+    }
+}
+
+impl Alerts {
+    fn into_alert(self) -> weather::Alert {
+        weather::Alert {
+            title: self.title,
+            description: self.description,
+            severity: match self.severity {
+                Severity::Advisory => Some(weather::Severity::Advisory),
+                Severity::Watch => Some(weather::Severity::Watch),
+                Severity::Warning => Some(weather::Severity::Warning),
+                Severity::Unknown => None,
+            },
+            regions: self.regions,
+            issued: self.time,
+            expires: Some(self.expires),
+            uri: Some(self.uri),
+        }
+    }
+}
+
+fn condition(icon: &Option<Icon>) -> Condition {
+    match icon {
+        Some(Icon::ClearDay) => Condition::ClearDay,
+        Some(Icon::ClearNight) => Condition::ClearNight,
+        Some(Icon::Rain) => Condition::Rain,
+        Some(Icon::Snow) => Condition::Snow,
+        Some(Icon::Sleet) => Condition::Sleet,
+        Some(Icon::Wind) => Condition::Wind,
+        Some(Icon::Fog) => Condition::Fog,
+        Some(Icon::Cloudy) => Condition::Cloudy,
+        Some(Icon::PartlyCloudyDay) => Condition::PartlyCloudyDay,
+        Some(Icon::PartlyCloudyNight) => Condition::PartlyCloudyNight,
+        Some(Icon::Unknown) | None => Condition::Unknown,
+    }
 }
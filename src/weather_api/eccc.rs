@@ -0,0 +1,345 @@
+use super::Config;
+use super::{Fetched, Fetcher, ForecastApi, Format, Provider, WeatherApi};
+use crate::cache::Endpoint;
+use crate::config::GenericWeatherUnit;
+use crate::weather::Report;
+use clap::ArgMatches;
+use failure::{err_msg, Error};
+use url::Url;
+
+mod models;
+
+pub use self::models::{EcccError, SiteData, SiteList};
+
+/// [Environment and Climate Change Canada](https://eccc-msc.github.io/open-data/msc-data/citypage-weather/readme_citypageweather-datamart_en/)'s
+/// citypages, which are XML and don't need an API key.
+///
+/// Each citypage is for a site, which is the closest one in the site list unless it's
+/// configured.
+#[derive(Debug, PartialEq)]
+pub struct Eccc<'a> {
+    pub coordinates: (f64, f64),
+    pub unit: GenericWeatherUnit,
+    /// `province/code`, e.g. `MB/s0000193`.
+    pub site: Option<&'a str>,
+}
+
+impl<'a> Eccc<'a> {
+    fn citypage_url(&self, site: &str) -> Url {
+        let (province, code) = site.split_once('/').unwrap_or(("", site));
+
+        let mut url = Url::parse(Self::BASE_URL).unwrap();
+        url.path_segments_mut()
+            .unwrap()
+            .extend(&["xml", province])
+            // English; the French ones end in `_f`.
+            .push(&format!("{}_e.xml", code));
+
+        url
+    }
+
+    /// The configured site, or else the one closest to the coordinates.
+    fn site(&self, fetcher: &Fetcher) -> Result<String, Error> {
+        if let Some(site) = self.site {
+            return Ok(site.to_string());
+        }
+
+        // The sites hardly ever change, so the list is cached like historical data.
+        let sites = fetcher.fetch_as::<SiteList, EcccError>(
+            Self::PROVIDER,
+            Format::Text,
+            Endpoint::Historical,
+            self.url(),
+            None,
+            self.headers(),
+        )?;
+        let site = sites
+            .value
+            .closest(self.coordinates)
+            .ok_or_else(|| err_msg("Environment Canada's site list is empty"))?;
+        debug!("using citypage {} ({})", site.code, site.name);
+
+        Ok(format!("{}/{}", site.province, site.code))
+    }
+
+    fn fetch_citypage(
+        &self,
+        fetcher: &Fetcher,
+        endpoint: Endpoint,
+    ) -> Result<Fetched<SiteData>, Error> {
+        let url = self.citypage_url(&self.site(fetcher)?);
+        fetcher.fetch_as::<SiteData, EcccError>(
+            Self::PROVIDER,
+            Format::Xml,
+            endpoint,
+            url,
+            None,
+            self.headers(),
+        )
+    }
+}
+
+impl<'a> WeatherApi<'a> for Eccc<'a> {
+    const BASE_URL: &'static str = "https://dd.weather.gc.ca/citypage_weather";
+    const PROVIDER: Provider = Provider::Eccc;
+    type Current = SiteData;
+    type ApiError = EcccError;
+
+    fn new(config: &'a Config, m: &'a ArgMatches) -> Self {
+        let eccc_matches = m.subcommand_matches("eccc");
+        let eccc_config = config.eccc.as_ref();
+
+        let coordinates = values_t!(m.values_of("coordinates"), f64)
+            .map(|coordinates| (coordinates[0], coordinates[1]))
+            .ok()
+            .or(config.coordinates)
+            .expect("tried creating eccc api without coordinates in config");
+
+        let unit = eccc_matches
+            .and_then(|eccc| value_t!(eccc.value_of("units"), GenericWeatherUnit).ok())
+            .or_else(|| value_t!(m.value_of("units"), GenericWeatherUnit).ok())
+            .or_else(|| eccc_config.and_then(|eccc| eccc.unit))
+            .or(config.unit)
+            .unwrap_or(GenericWeatherUnit::Metric);
+
+        let site = eccc_matches
+            .and_then(|eccc| eccc.value_of("site"))
+            .or_else(|| eccc_config.and_then(|eccc| eccc.site.as_deref()));
+
+        Self {
+            coordinates,
+            unit,
+            site,
+        }
+    }
+
+    /// The site list.
+    fn url(&self) -> Url {
+        let mut url = Url::parse(Self::BASE_URL).unwrap();
+        url.path_segments_mut()
+            .unwrap()
+            .extend(&["docs", "site_list_en.csv"]);
+
+        url
+    }
+
+    /// The citypage, when the site is known, or else the site list to find it in.
+    fn current_url(&self) -> Url {
+        match self.site {
+            Some(site) => self.citypage_url(site),
+            None => self.url(),
+        }
+    }
+
+    fn report(&self, current: SiteData) -> Report {
+        current.into_report(self.unit)
+    }
+
+    fn fetch_current(&self, fetcher: &Fetcher) -> Result<Fetched<SiteData>, Error> {
+        self.fetch_citypage(fetcher, Endpoint::Current)
+    }
+}
+
+impl<'a> ForecastApi<'a> for Eccc<'a> {
+    type Forecast = SiteData;
+
+    /// The forecast is on the same citypage as the current conditions.
+    fn forecast_url(&self) -> Url {
+        self.current_url()
+    }
+
+    fn forecast_report(&self, forecast: SiteData) -> Report {
+        forecast.into_report(self.unit)
+    }
+
+    fn fetch_forecast(&self, fetcher: &Fetcher) -> Result<Fetched<SiteData>, Error> {
+        self.fetch_citypage(fetcher, Endpoint::Forecast)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EcccConfig;
+    use crate::weather::{
+        Condition, Pressure, PressureUnit, Severity, Temperature, TemperatureUnit,
+    };
+
+    const CITYPAGE: &str = r#"<?xml version="1.0" encoding="ISO-8859-1"?>
+<siteData xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <license>https://dd.weather.gc.ca/doc/LICENCE_GENERAL.txt</license>
+  <dateTime name="xmlCreation" zone="UTC" UTCOffset="0">
+    <year>2018</year><timeStamp>20181113210000</timeStamp>
+  </dateTime>
+  <location>
+    <continent>North America</continent>
+    <country code="ca">Canada</country>
+    <province code="MB">Manitoba</province>
+    <name code="s0000193" lat="49.88N" lon="97.15W">Winnipeg</name>
+    <region>City of Winnipeg</region>
+  </location>
+  <warnings url="https://weather.gc.ca/warnings/report_e.html?mb50">
+    <event type="warning" priority="high" description="BLIZZARD WARNING  IN EFFECT">
+      <dateTime name="eventIssue" zone="UTC" UTCOffset="0">
+        <timeStamp>20181113200000</timeStamp>
+        <textSummary>Tuesday November 13, 2018 at 20:00 UTC</textSummary>
+      </dateTime>
+      <dateTime name="eventIssue" zone="CST" UTCOffset="-6">
+        <timeStamp>20181113140000</timeStamp>
+      </dateTime>
+    </event>
+    <event type="ended" priority="low" description="SNOWFALL WARNING  ENDED"/>
+  </warnings>
+  <currentConditions>
+    <station code="ywg" lat="49.92N" lon="97.23W">Winnipeg Richardson Int'l Airport</station>
+    <dateTime name="observation" zone="UTC" UTCOffset="0">
+      <timeStamp>20181113210000</timeStamp>
+    </dateTime>
+    <dateTime name="observation" zone="CST" UTCOffset="-6">
+      <timeStamp>20181113150000</timeStamp>
+    </dateTime>
+    <condition>Light Snow</condition>
+    <iconCode format="gif">16</iconCode>
+    <temperature unitType="metric" units="C">-3.5</temperature>
+    <dewpoint unitType="metric" units="C">-7.1</dewpoint>
+    <windChill unitType="metric">-10</windChill>
+    <pressure unitType="metric" units="kPa" change="0.10" tendency="rising">102.1</pressure>
+    <visibility unitType="metric" units="km">8.0</visibility>
+    <relativeHumidity units="%">74</relativeHumidity>
+    <wind>
+      <speed unitType="metric" units="km/h">18</speed>
+      <gust unitType="metric" units="km/h"></gust>
+      <direction>NW</direction>
+      <bearing units="degrees">310.0</bearing>
+    </wind>
+  </currentConditions>
+  <forecastGroup>
+    <dateTime name="forecastIssue" zone="UTC" UTCOffset="0">
+      <timeStamp>20181113163000</timeStamp>
+    </dateTime>
+    <dateTime name="forecastIssue" zone="CST" UTCOffset="-6">
+      <timeStamp>20181113103000</timeStamp>
+    </dateTime>
+    <forecast>
+      <period textForecastName="Today">Tuesday</period>
+      <textSummary>Snow. High minus 1.</textSummary>
+      <abbreviatedForecast>
+        <iconCode format="gif">16</iconCode>
+        <pop units="%">70</pop>
+        <textSummary>Snow</textSummary>
+      </abbreviatedForecast>
+      <temperatures>
+        <textSummary>High minus 1.</textSummary>
+        <temperature unitType="metric" units="C" class="high">-1</temperature>
+      </temperatures>
+    </forecast>
+    <forecast>
+      <period textForecastName="Tonight">Tuesday night</period>
+      <textSummary>Clearing. Low minus 12.</textSummary>
+      <abbreviatedForecast>
+        <iconCode format="gif">35</iconCode>
+        <pop units="%"></pop>
+      </abbreviatedForecast>
+      <temperatures>
+        <temperature unitType="metric" units="C" class="low">-12</temperature>
+      </temperatures>
+    </forecast>
+    <forecast>
+      <period textForecastName="Wednesday">Wednesday</period>
+      <textSummary>Sunny. High minus 6.</textSummary>
+      <abbreviatedForecast><iconCode format="gif">00</iconCode></abbreviatedForecast>
+      <temperatures>
+        <temperature unitType="metric" units="C" class="high">-6</temperature>
+      </temperatures>
+    </forecast>
+  </forecastGroup>
+</siteData>"#;
+
+    #[test]
+    fn it_creates_new_eccc_with_a_site() {
+        let config = Config {
+            coordinates: Some((49.88, -97.15)),
+            eccc: Some(EcccConfig {
+                site: Some(String::from("MB/s0000193")),
+                unit: None,
+            }),
+            ..Default::default()
+        };
+
+        let matches = clap::App::new("name").get_matches_from(vec!["name"]);
+        let api = Eccc::new(&config, &matches);
+
+        assert_eq!(Some("MB/s0000193"), api.site);
+        assert_eq!(
+            Url::parse("https://dd.weather.gc.ca/citypage_weather/xml/MB/s0000193_e.xml").unwrap(),
+            api.current_url()
+        );
+    }
+
+    #[test]
+    fn it_finds_the_closest_site() {
+        let sites = SiteList::parse(
+            "Site Names,,,,\n\
+             Codes,English Names,Province Codes,Latitude,Longitude\n\
+             s0000193,Winnipeg,MB,49.88N,97.15W\n\
+             s0000458,Toronto,ON,43.74N,79.37W\n\
+             s0000047,Brandon,MB,49.85N,99.95W\n",
+        )
+        .unwrap();
+
+        assert_eq!(3, sites.0.len());
+        assert_eq!(
+            Some("s0000193"),
+            sites.closest((49.9, -97.14)).map(|site| site.code.as_str())
+        );
+        assert_eq!(
+            Some("s0000047"),
+            sites.closest((50., -100.)).map(|site| site.code.as_str())
+        );
+    }
+
+    #[test]
+    fn it_normalizes_a_citypage() {
+        let citypage: SiteData = quick_xml::de::from_str(CITYPAGE).unwrap();
+        let api = Eccc {
+            coordinates: (49.88, -97.15),
+            unit: GenericWeatherUnit::Metric,
+            site: Some("MB/s0000193"),
+        };
+        let report = api.forecast_report(citypage);
+        let current = report.current.unwrap();
+
+        assert_eq!(Some(String::from("Winnipeg, MB")), report.location.name);
+        assert_eq!(Some(-97.15), report.location.longitude);
+        assert_eq!(1_542_142_800, current.time);
+        assert_eq!(Condition::Snow, current.condition);
+        assert_eq!(
+            Some(Temperature::new(-10., TemperatureUnit::Celsius)),
+            current.apparent_temperature
+        );
+        assert_eq!(
+            Some(Pressure::new(1021., PressureUnit::Hectopascals)),
+            current.pressure
+        );
+        assert_eq!(None, current.wind_gust);
+        assert_eq!(Some(310.), current.wind_bearing);
+
+        assert_eq!(2, report.daily.len());
+        assert_eq!(1_542_088_800, report.daily[0].time);
+        assert_eq!(
+            Some(Temperature::new(-1., TemperatureUnit::Celsius)),
+            report.daily[0].temperature_high
+        );
+        assert_eq!(
+            Some(Temperature::new(-12., TemperatureUnit::Celsius)),
+            report.daily[0].temperature_low
+        );
+        assert_eq!(Some(0.7), report.daily[0].precip_probability);
+        assert_eq!(Condition::ClearDay, report.daily[1].condition);
+
+        assert_eq!(1, report.alerts.len());
+        assert_eq!(Some(Severity::Warning), report.alerts[0].severity);
+        assert_eq!(1_542_139_200, report.alerts[0].issued);
+        assert_eq!(vec!["City of Winnipeg"], report.alerts[0].regions);
+    }
+}
@@ -0,0 +1,528 @@
+use crate::config::GenericWeatherUnit;
+use crate::weather::{
+    self, Condition, DailySummary, Distance, DistanceUnit, Location, Observation, Pressure,
+    PressureUnit, Report, Severity, Speed, SpeedUnit, Temperature, TemperatureUnit, Units,
+};
+use crate::weather_api::Provider;
+use chrono::{NaiveDate, NaiveDateTime};
+use failure::Fail;
+use serde::de::{Deserializer, Error as _};
+use serde_derive::Deserialize;
+use std::fmt;
+
+/// What's left of an error page, when it happens to be XML.
+#[derive(Fail, Debug, Deserialize)]
+pub struct EcccError {
+    #[serde(rename = "$text", default)]
+    message: Option<String>,
+}
+
+impl fmt::Display for EcccError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "environment canada error: {}",
+            self.message
+                .as_ref()
+                .map_or("unknown error", String::as_str)
+        )
+    }
+}
+
+/// `site_list_en.csv`: every citypage, and where it is.
+#[derive(Debug, PartialEq)]
+pub struct SiteList(pub Vec<Site>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Site {
+    /// e.g. `s0000193`.
+    pub code: String,
+    pub name: String,
+    /// e.g. `MB`.
+    pub province: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// A citypage: `/citypage_weather/xml/{province}/{code}_e.xml`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteData {
+    #[serde(rename = "dateTime", default)]
+    #[allow(dead_code)]
+    date_times: Vec<DateTime>,
+    location: SiteLocation,
+    warnings: Option<Warnings>,
+    current_conditions: Option<CurrentConditions>,
+    forecast_group: Option<ForecastGroup>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DateTime {
+    /// e.g. `observation` or `forecastIssue`.
+    #[serde(rename = "@name")]
+    #[allow(dead_code)]
+    name: String,
+    /// `UTC`, or the local zone's abbreviation.
+    #[serde(rename = "@zone")]
+    zone: String,
+    #[serde(rename = "@UTCOffset")]
+    utc_offset: f64,
+    /// e.g. `20181113210000`.
+    #[serde(rename = "timeStamp")]
+    time_stamp: String,
+    #[serde(rename = "textSummary")]
+    #[allow(dead_code)]
+    text_summary: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SiteLocation {
+    #[allow(dead_code)]
+    continent: Option<String>,
+    #[allow(dead_code)]
+    country: Option<Coded>,
+    province: Option<Coded>,
+    name: Option<Coded>,
+    region: Option<String>,
+}
+
+/// An element with a code, like `<province code="MB">Manitoba</province>`.
+#[derive(Debug, Deserialize)]
+pub struct Coded {
+    #[serde(rename = "@code")]
+    code: Option<String>,
+    #[serde(rename = "@lat")]
+    lat: Option<String>,
+    #[serde(rename = "@lon")]
+    lon: Option<String>,
+    #[serde(rename = "$text")]
+    text: Option<String>,
+}
+
+/// A measurement, like `<temperature unitType="metric" units="C">-3.5</temperature>`. It's empty
+/// when there's nothing to report.
+#[derive(Debug, Deserialize)]
+pub struct Value {
+    #[serde(rename = "@units")]
+    #[allow(dead_code)]
+    units: Option<String>,
+    /// `high` or `low`, for forecast temperatures.
+    #[serde(rename = "@class")]
+    class: Option<String>,
+    #[serde(rename = "$text")]
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Warnings {
+    #[serde(rename = "@url")]
+    url: Option<String>,
+    #[serde(rename = "event", default)]
+    events: Vec<Event>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Event {
+    /// `warning`, `watch`, `advisory`, `statement` or `ended`.
+    #[serde(rename = "@type")]
+    kind: String,
+    #[serde(rename = "@priority")]
+    #[allow(dead_code)]
+    priority: Option<String>,
+    /// e.g. `BLIZZARD WARNING IN EFFECT`.
+    #[serde(rename = "@description")]
+    description: String,
+    #[serde(rename = "dateTime", default)]
+    date_times: Vec<DateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentConditions {
+    #[allow(dead_code)]
+    station: Option<Coded>,
+    #[serde(rename = "dateTime", default)]
+    date_times: Vec<DateTime>,
+    condition: Option<String>,
+    icon_code: Option<Value>,
+    temperature: Option<Value>,
+    dewpoint: Option<Value>,
+    wind_chill: Option<Value>,
+    humidex: Option<Value>,
+    /// In kPa.
+    pressure: Option<Value>,
+    visibility: Option<Value>,
+    relative_humidity: Option<Value>,
+    wind: Option<Wind>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Wind {
+    speed: Option<Value>,
+    gust: Option<Value>,
+    /// A compass direction, e.g. `NW`.
+    #[allow(dead_code)]
+    direction: Option<String>,
+    bearing: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastGroup {
+    #[serde(rename = "dateTime", default)]
+    date_times: Vec<DateTime>,
+    #[serde(rename = "forecast", default)]
+    forecasts: Vec<Forecast>,
+}
+
+/// A day or night in the forecast group.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Forecast {
+    #[allow(dead_code)]
+    period: Period,
+    text_summary: Option<String>,
+    abbreviated_forecast: Option<AbbreviatedForecast>,
+    temperatures: Option<Temperatures>,
+    relative_humidity: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Period {
+    /// e.g. `Tonight` or `Wednesday night`.
+    #[serde(rename = "@textForecastName")]
+    #[allow(dead_code)]
+    name: Option<String>,
+    #[serde(rename = "$text")]
+    #[allow(dead_code)]
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbbreviatedForecast {
+    icon_code: Option<Value>,
+    /// Probability of precipitation, in percent.
+    pop: Option<Value>,
+    #[allow(dead_code)]
+    text_summary: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Temperatures {
+    #[allow(dead_code)]
+    text_summary: Option<String>,
+    #[serde(rename = "temperature", default)]
+    temperatures: Vec<Value>,
+}
+
+impl<'de> serde::Deserialize<'de> for SiteList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let body = <String as serde::Deserialize>::deserialize(deserializer)?;
+        SiteList::parse(&body).map_err(D::Error::custom)
+    }
+}
+
+impl SiteList {
+    /// The site list starts with a title and a header, which are skipped along with any other
+    /// line that isn't a site.
+    pub fn parse(csv: &str) -> Result<Self, csv::Error> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(csv.as_bytes());
+
+        let mut sites = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            let field = |i| record.get(i).map(str::trim).unwrap_or_default();
+
+            if let (Some(latitude), Some(longitude)) = (coordinate(field(3)), coordinate(field(4)))
+            {
+                sites.push(Site {
+                    code: field(0).to_string(),
+                    name: field(1).to_string(),
+                    province: field(2).to_string(),
+                    latitude,
+                    longitude,
+                });
+            }
+        }
+
+        Ok(SiteList(sites))
+    }
+
+    /// The site closest to `coordinates`.
+    pub fn closest(&self, coordinates: (f64, f64)) -> Option<&Site> {
+        let (latitude, longitude) = coordinates;
+        // Close enough to a flat map at these distances.
+        let scale = latitude.to_radians().cos();
+        let distance = |site: &Site| {
+            (site.latitude - latitude).powi(2) + ((site.longitude - longitude) * scale).powi(2)
+        };
+
+        self.0
+            .iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+    }
+}
+
+impl SiteData {
+    pub fn into_report(self, unit: GenericWeatherUnit) -> Report {
+        let units = Units::from(unit);
+        let mut report = Report::new(Provider::Eccc, units);
+        let location = self.location;
+        let region = location.region.clone();
+        let name = location.name.as_ref();
+
+        report.location = Location {
+            name: name.and_then(|name| name.text.clone()).map(|name| {
+                match location.province.as_ref().and_then(|p| p.code.as_ref()) {
+                    Some(province) => format!("{}, {}", name, province),
+                    None => name,
+                }
+            }),
+            country: Some(String::from("CA")),
+            latitude: name
+                .and_then(|name| name.lat.as_deref())
+                .and_then(coordinate),
+            longitude: name
+                .and_then(|name| name.lon.as_deref())
+                .and_then(coordinate),
+            ..Default::default()
+        };
+
+        report.current = self
+            .current_conditions
+            .and_then(|current| current.into_observation(units));
+        report.daily = self
+            .forecast_group
+            .map(|group| group.into_daily(units))
+            .unwrap_or_default();
+        report.alerts = self
+            .warnings
+            .map(|warnings| {
+                let url = warnings.url;
+                warnings
+                    .events
+                    .into_iter()
+                    .filter_map(|event| event.into_alert(&url, &region))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        report
+    }
+}
+
+impl DateTime {
+    /// The timestamp, when this is the UTC one of the pair.
+    fn utc(&self) -> Option<i64> {
+        if self.zone != "UTC" {
+            return None;
+        }
+
+        NaiveDateTime::parse_from_str(&self.time_stamp, "%Y%m%d%H%M%S")
+            .ok()
+            .map(|time| time.and_utc().timestamp())
+    }
+
+    /// Midnight at the start of the local day, as a timestamp, when this is the local one of the
+    /// pair.
+    fn local_midnight(&self) -> Option<i64> {
+        if self.zone == "UTC" {
+            return None;
+        }
+
+        let date = NaiveDate::parse_from_str(self.time_stamp.get(..8)?, "%Y%m%d").ok()?;
+        let midnight = date.and_hms_opt(0, 0, 0)?.and_utc().timestamp();
+
+        Some(midnight - (self.utc_offset * 60. * 60.) as i64)
+    }
+}
+
+impl Value {
+    fn number(&self) -> Option<f64> {
+        self.text.as_ref()?.trim().parse().ok()
+    }
+}
+
+impl CurrentConditions {
+    /// `None` when the station isn't reporting.
+    fn into_observation(self, units: Units) -> Option<Observation> {
+        let time = self.date_times.iter().find_map(DateTime::utc)?;
+        let number = |value: &Option<Value>| value.as_ref().and_then(Value::number);
+        let wind = self.wind.as_ref();
+
+        Some(Observation {
+            summary: self.condition.clone(),
+            condition: condition(number(&self.icon_code)),
+            temperature: number(&self.temperature).map(|t| temperature(t, units)),
+            apparent_temperature: number(&self.wind_chill)
+                .or_else(|| number(&self.humidex))
+                .map(|t| temperature(t, units)),
+            dew_point: number(&self.dewpoint).map(|t| temperature(t, units)),
+            humidity: number(&self.relative_humidity).map(|humidity| humidity / 100.),
+            pressure: number(&self.pressure)
+                .map(|kpa| Pressure::new(kpa, PressureUnit::Kilopascals).to(units.pressure)),
+            wind_speed: wind
+                .and_then(|wind| number(&wind.speed))
+                .map(|speed| wind_speed(speed, units)),
+            wind_gust: wind
+                .and_then(|wind| number(&wind.gust))
+                .map(|speed| wind_speed(speed, units)),
+            wind_bearing: wind.and_then(|wind| number(&wind.bearing)),
+            visibility: number(&self.visibility)
+                .map(|km| Distance::new(km, DistanceUnit::Kilometres).to(units.distance)),
+            ..Observation::new(time)
+        })
+    }
+}
+
+impl ForecastGroup {
+    /// The forecast goes by named periods ("Tonight", "Wednesday", "Wednesday night"), so a day
+    /// and the night after it become one summary, counting days from when it was issued.
+    fn into_daily(self, units: Units) -> Vec<DailySummary> {
+        let start = self
+            .date_times
+            .iter()
+            .find_map(DateTime::local_midnight)
+            .unwrap_or_default();
+        let mut days: Vec<DailySummary> = Vec::new();
+        let mut previous_was_day = false;
+
+        for forecast in &self.forecasts {
+            let (high, low) = forecast.temperatures(units);
+            let night = high.is_none() && low.is_some();
+            let abbreviated = forecast.abbreviated_forecast.as_ref();
+            let chance = abbreviated
+                .and_then(|abbreviated| abbreviated.pop.as_ref())
+                .and_then(Value::number)
+                .map(|pop| pop / 100.);
+
+            match days.last_mut() {
+                Some(day) if previous_was_day && night => {
+                    day.temperature_low = low;
+                    day.precip_probability = match (day.precip_probability, chance) {
+                        (Some(day), Some(night)) => Some(day.max(night)),
+                        (day, night) => day.or(night),
+                    };
+                }
+                _ => {
+                    let time = start + days.len() as i64 * 24 * 60 * 60;
+                    days.push(DailySummary {
+                        summary: forecast.text_summary.clone(),
+                        condition: condition(
+                            abbreviated
+                                .and_then(|abbreviated| abbreviated.icon_code.as_ref())
+                                .and_then(Value::number),
+                        ),
+                        temperature_high: high,
+                        temperature_low: low,
+                        humidity: forecast
+                            .relative_humidity
+                            .as_ref()
+                            .and_then(Value::number)
+                            .map(|humidity| humidity / 100.),
+                        precip_probability: chance,
+                        ..DailySummary::new(time)
+                    });
+                }
+            }
+            previous_was_day = !night;
+        }
+
+        days
+    }
+}
+
+impl Forecast {
+    fn temperatures(&self, units: Units) -> (Option<Temperature>, Option<Temperature>) {
+        let temperatures = self
+            .temperatures
+            .as_ref()
+            .map_or(&[][..], |temperatures| &temperatures.temperatures);
+        let find = |class: &str| {
+            temperatures
+                .iter()
+                .find(|value| value.class.as_deref() == Some(class))
+                .and_then(Value::number)
+                .map(|t| temperature(t, units))
+        };
+
+        (find("high"), find("low"))
+    }
+}
+
+impl Event {
+    /// `None` for warnings that have ended.
+    fn into_alert(self, url: &Option<String>, region: &Option<String>) -> Option<weather::Alert> {
+        let severity = match self.kind.as_str() {
+            "ended" => return None,
+            "warning" => Some(Severity::Warning),
+            "watch" => Some(Severity::Watch),
+            "advisory" => Some(Severity::Advisory),
+            _ => None,
+        };
+
+        Some(weather::Alert {
+            title: self.description.clone(),
+            description: self.description,
+            severity,
+            regions: region.iter().cloned().collect(),
+            issued: self
+                .date_times
+                .iter()
+                .find_map(DateTime::utc)
+                .unwrap_or_default(),
+            expires: None,
+            uri: url.clone(),
+        })
+    }
+}
+
+/// A coordinate like `49.88N` or `97.15W`.
+fn coordinate(coordinate: &str) -> Option<f64> {
+    let sign = match coordinate.chars().last()? {
+        'N' | 'E' => 1.,
+        'S' | 'W' => -1.,
+        _ => return None,
+    };
+
+    coordinate[..coordinate.len() - 1]
+        .parse::<f64>()
+        .ok()
+        .map(|value| value * sign)
+}
+
+/// Everything comes in metric units.
+fn temperature(celsius: f64, units: Units) -> Temperature {
+    Temperature::new(celsius, TemperatureUnit::Celsius).to(units.temperature)
+}
+
+fn wind_speed(kilometres_per_hour: f64, units: Units) -> Speed {
+    Speed::new(kilometres_per_hour, SpeedUnit::KilometresPerHour).to(units.speed)
+}
+
+/// Map an icon code onto a [`Condition`].
+///
+/// See <https://eccc-msc.github.io/open-data/msc-data/citypage-weather/readme_citypageweather-datamart_en/>.
+fn condition(code: Option<f64>) -> Condition {
+    match code.map(|code| code as u8) {
+        Some(0) | Some(1) => Condition::ClearDay,
+        Some(30) | Some(31) => Condition::ClearNight,
+        Some(2) | Some(4) | Some(5) | Some(22) => Condition::PartlyCloudyDay,
+        Some(32) | Some(34) | Some(35) => Condition::PartlyCloudyNight,
+        Some(3) | Some(10) | Some(33) => Condition::Cloudy,
+        Some(6) | Some(11) | Some(12) | Some(13) | Some(28) | Some(36) => Condition::Rain,
+        Some(7) | Some(14) | Some(15) | Some(27) | Some(37) => Condition::Sleet,
+        Some(8) | Some(16) | Some(17) | Some(18) | Some(25) | Some(26) | Some(38) | Some(40) => {
+            Condition::Snow
+        }
+        Some(9) | Some(19) | Some(39) | Some(46) | Some(47) => Condition::Thunderstorm,
+        Some(23) | Some(24) | Some(44) | Some(45) => Condition::Fog,
+        Some(41) | Some(42) | Some(43) | Some(48) => Condition::Wind,
+        _ => Condition::Unknown,
+    }
+}
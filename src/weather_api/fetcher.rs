@@ -0,0 +1,631 @@
+use super::Provider;
+use crate::cache::{self, Cache, Endpoint, Validity};
+use crate::config::RetryConfig;
+use crate::usage::{BudgetReached, Ledger};
+use crate::weather::Report;
+use chrono::{DateTime, Utc};
+use failure::{err_msg, Error, Fail};
+use reqwest::header::{
+    HeaderMap, HeaderValue, EXPIRES, IF_MODIFIED_SINCE, LAST_MODIFIED, RETRY_AFTER,
+};
+use reqwest::{Client, StatusCode};
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use std::thread;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Fetches and decodes API responses, going through the cache when there is one.
+#[derive(Debug)]
+pub struct Fetcher<'a> {
+    client: &'a Client,
+    cache: Option<Cache<'a>>,
+    /// No requests or retries are started after this.
+    deadline: Option<Instant>,
+    /// Requests are only tried once without this.
+    retry: Option<&'a RetryConfig>,
+    /// Where calls are counted, and daily budgets kept to.
+    ledger: Option<&'a Ledger<'a>>,
+}
+
+#[derive(Fail, Debug)]
+#[fail(display = "ran out of time before fetching {}", _0)]
+pub struct DeadlinePassed(String);
+
+/// How a response body is decoded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Xml,
+    /// The body as it is, for types that parse it themselves from a string.
+    Text,
+}
+
+/// A decoded response, and where it came from.
+#[derive(Debug)]
+pub struct Fetched<T> {
+    pub value: T,
+    /// Seconds since the response was fetched, when it came from the cache.
+    pub age: Option<u64>,
+    /// The API couldn't be reached, so this is an expired response from the cache.
+    pub stale: bool,
+}
+
+impl<T> Fetched<T> {
+    /// Convert the value into a report that remembers how fresh it is.
+    pub fn into_report<F>(self, f: F) -> Report
+    where
+        F: FnOnce(T) -> Report,
+    {
+        let mut report = f(self.value);
+        report.age = self.age;
+        report.stale = self.stale;
+
+        report
+    }
+
+    /// Put two responses together, as fresh as the older one.
+    pub fn and<U>(self, other: Fetched<U>) -> Fetched<(T, U)> {
+        Fetched {
+            value: (self.value, other.value),
+            age: self.age.max(other.age),
+            stale: self.stale || other.stale,
+        }
+    }
+
+    pub fn map<U, F>(self, f: F) -> Fetched<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Fetched {
+            value: f(self.value),
+            age: self.age,
+            stale: self.stale,
+        }
+    }
+}
+
+impl<'a> Fetcher<'a> {
+    pub fn new(client: &'a Client, cache: Option<Cache<'a>>) -> Self {
+        Self {
+            client,
+            cache,
+            deadline: None,
+            retry: None,
+            ledger: None,
+        }
+    }
+
+    /// Try requests again when they fail for a reason that might pass.
+    pub fn retry(self, retry: &'a RetryConfig) -> Self {
+        Self {
+            retry: Some(retry),
+            ..self
+        }
+    }
+
+    /// Count every call in `ledger`. Once a provider's daily budget is used up, cached responses
+    /// are used however old they are, like when the API can't be reached.
+    pub fn ledger(self, ledger: &'a Ledger<'a>) -> Self {
+        Self {
+            ledger: Some(ledger),
+            ..self
+        }
+    }
+
+    /// Stop starting requests at `deadline`, and don't wait to retry past it. A request that's
+    /// already going then still has the client's timeout. After that, cached responses are used
+    /// however old they are, like when the API can't be reached.
+    pub fn until(self, deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+
+    /// Fetch `url` and decode it from JSON into `T`, or into the API's error `E` on a client
+    /// error.
+    ///
+    /// `secret` is the API key, if it's part of the URL. It's kept out of the cache and the logs.
+    /// `headers` are sent along with the request, but aren't part of the cache key.
+    ///
+    /// When the API can't be reached (or has a server error), the most recent cached response is
+    /// used instead, however old it is. Client errors like a bad key are never hidden this way,
+    /// whether or not they come as one of the API's errors.
+    ///
+    /// Responses are cached for the endpoint's TTL, or until their `Expires` header when the
+    /// provider [asks for that](Provider::follows_expires). After that, a response with a
+    /// `Last-Modified` header is only fetched again if the API says it changed.
+    ///
+    /// With [`retry`](Self::retry), requests that fail for a reason that might pass are tried
+    /// again, waiting as long as `Retry-After` asks when the API sends it.
+    pub fn fetch<T, E>(
+        &self,
+        provider: Provider,
+        endpoint: Endpoint,
+        url: Url,
+        secret: Option<&str>,
+        headers: HeaderMap,
+    ) -> Result<Fetched<T>, Error>
+    where
+        T: DeserializeOwned,
+        E: DeserializeOwned + failure::Fail,
+    {
+        self.fetch_as::<T, E>(provider, Format::Json, endpoint, url, secret, headers)
+    }
+
+    /// Like [`fetch`](Self::fetch), but in some other format.
+    pub fn fetch_as<T, E>(
+        &self,
+        provider: Provider,
+        format: Format,
+        endpoint: Endpoint,
+        url: Url,
+        secret: Option<&str>,
+        mut headers: HeaderMap,
+    ) -> Result<Fetched<T>, Error>
+    where
+        T: DeserializeOwned,
+        E: DeserializeOwned + failure::Fail,
+    {
+        let key = cache::key(url.as_str(), secret);
+        let decode = |cached: cache::Cached| match format.decode::<T>(&cached.body) {
+            Ok(value) => Some((value, cached.age)),
+            Err(e) => {
+                debug!("couldn't decode cached response for {}: {}", key, e);
+                None
+            }
+        };
+
+        if let Some(cached) = self.cache.as_ref().and_then(|c| c.get(endpoint, &key)) {
+            if let Some((value, age)) = decode(cached) {
+                return Ok(Fetched {
+                    value,
+                    age: Some(age),
+                    stale: false,
+                });
+            }
+        }
+
+        let last_modified = self
+            .cache
+            .as_ref()
+            .and_then(|c| c.get_stale(&key))
+            .and_then(|cached| cached.last_modified)
+            .and_then(|last_modified| HeaderValue::from_str(&last_modified).ok());
+        if let Some(last_modified) = last_modified {
+            headers.insert(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let mut response = match self.send::<E>(provider, format, &key, secret, url, headers) {
+            Ok(response) => response,
+            Err(e) => {
+                if !unreachable(&e) {
+                    return Err(e);
+                }
+
+                let stale = self
+                    .cache
+                    .as_ref()
+                    .and_then(|c| c.get_stale(&key))
+                    .and_then(decode);
+
+                return match stale {
+                    Some((value, age)) => {
+                        warn!("{}; using a cached response from {}s ago", e, age);
+                        Ok(Fetched {
+                            value,
+                            age: Some(age),
+                            stale: true,
+                        })
+                    }
+                    None => Err(e),
+                };
+            }
+        };
+        if !provider.follows_expires() {
+            response.validity.expires = None;
+        }
+        let body = match response.body {
+            Some(body) => body,
+            None => {
+                debug!("{} hasn't changed since it was cached", key);
+                let cache = self
+                    .cache
+                    .as_ref()
+                    .ok_or_else(|| err_msg("not modified, but there's no cache"))?;
+                if let Err(e) = cache.touch(&key, response.validity) {
+                    warn!("couldn't update cached response for {}: {}", key, e);
+                }
+
+                return cache
+                    .get_stale(&key)
+                    .and_then(decode)
+                    .map(|(value, _)| Fetched {
+                        value,
+                        age: None,
+                        stale: false,
+                    })
+                    .ok_or_else(|| err_msg(format!("{} is no longer cached", key)));
+            }
+        };
+        let value = format.decode::<T>(&body)?;
+
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.put(&key, &body, response.validity) {
+                warn!("couldn't cache response for {}: {}", key, e);
+            }
+        }
+
+        Ok(Fetched {
+            value,
+            age: None,
+            stale: false,
+        })
+    }
+
+    /// Fetch `url`, trying again while it fails for a reason that might pass and there's time.
+    fn send<E>(
+        &self,
+        provider: Provider,
+        format: Format,
+        key: &str,
+        secret: Option<&str>,
+        url: Url,
+        headers: HeaderMap,
+    ) -> Result<Response, Error>
+    where
+        E: DeserializeOwned + failure::Fail,
+    {
+        let out_of_time = |wait| {
+            self.deadline
+                .is_some_and(|deadline| Instant::now() + wait >= deadline)
+        };
+        let attempts = self.retry.map_or(1, RetryConfig::attempts);
+        let mut retry = 0;
+
+        loop {
+            if out_of_time(Duration::from_secs(0)) {
+                return Err(DeadlinePassed(key.to_string()).into());
+            }
+            if let Some(ledger) = self.ledger {
+                ledger.check(provider, secret)?;
+            }
+
+            info!("fetching {}", key);
+            let result = fetch_text::<E>(self.client, format, url.clone(), headers.clone());
+
+            let answered = match &result {
+                Ok(response) => Some(response.api_calls),
+                Err(failure) if failure.answered => Some(None),
+                Err(_) => None,
+            };
+            if let (Some(ledger), Some(api_calls)) = (self.ledger, answered) {
+                if let Err(e) = ledger.record(provider, secret, api_calls) {
+                    warn!("couldn't record the call to {}: {}", provider.name(), e);
+                }
+            }
+
+            let failure = match result {
+                Ok(response) => return Ok(response),
+                Err(failure) => failure,
+            };
+
+            let retry_config = match self.retry {
+                Some(retry_config) if failure.retryable && retry + 1 < attempts => retry_config,
+                _ => return Err(failure.error),
+            };
+            let wait = failure
+                .retry_after
+                .unwrap_or_else(|| retry_config.backoff(retry));
+            if wait > retry_config.max_backoff() || out_of_time(wait) {
+                debug!("not waiting {:?} to try {} again", wait, key);
+                return Err(failure.error);
+            }
+
+            warn!(
+                "{}; trying again in {:.1}s",
+                failure.error,
+                wait.as_secs_f64()
+            );
+            thread::sleep(wait);
+            retry += 1;
+        }
+    }
+}
+
+/// Why a request failed, and whether it's worth trying again.
+struct Failure {
+    error: Error,
+    /// Connection errors, timeouts, server errors and rate limits are; API errors aren't.
+    retryable: bool,
+    /// How long the API asked to wait first, with `Retry-After`.
+    retry_after: Option<Duration>,
+    /// Whether the API answered at all, so the call counts against its limits.
+    answered: bool,
+}
+
+impl Failure {
+    fn new(error: impl Into<Error>, retryable: bool) -> Self {
+        Self {
+            error: error.into(),
+            retryable,
+            retry_after: None,
+            answered: true,
+        }
+    }
+}
+
+/// A response body, and what its headers said about caching it.
+struct Response {
+    /// `None` when the API says the cached response hasn't changed (`304 Not Modified`).
+    body: Option<String>,
+    validity: Validity,
+    /// The day's calls, as the API counts them (DarkSky's `X-Forecast-API-Calls`).
+    api_calls: Option<u64>,
+}
+
+impl Format {
+    fn decode<T: DeserializeOwned>(self, body: &str) -> Result<T, Error> {
+        Ok(match self {
+            Format::Json => serde_json::from_str(body)?,
+            Format::Xml => quick_xml::de::from_str(body)?,
+            Format::Text => T::deserialize(
+                IntoDeserializer::<serde::de::value::Error>::into_deserializer(body),
+            )?,
+        })
+    }
+}
+
+fn fetch_text<E>(
+    client: &Client,
+    format: Format,
+    url: Url,
+    headers: HeaderMap,
+) -> Result<Response, Failure>
+where
+    E: DeserializeOwned + failure::Fail,
+{
+    let mut res = client
+        .get(url)
+        .headers(headers)
+        .send()
+        .map_err(|e| Failure {
+            answered: false,
+            ..Failure::new(e, true)
+        })?;
+    let text = |res: &mut reqwest::Response| res.text().map_err(|e| Failure::new(e, true));
+
+    let validity = validity(res.headers());
+    let api_calls = api_calls(res.headers());
+    match res.status() {
+        StatusCode::NOT_MODIFIED => Ok(Response {
+            body: None,
+            validity,
+            api_calls,
+        }),
+        status if status.is_success() => Ok(Response {
+            body: Some(text(&mut res)?),
+            validity,
+            api_calls,
+        }),
+        status if status.is_client_error() => {
+            // When the body isn't one of the API's errors, the status will have to do.
+            let status_error = res.error_for_status_ref().unwrap_err();
+            let retry_after = retry_after(res.headers());
+            match format.decode::<E>(&text(&mut res)?) {
+                Ok(e) => Err(Failure::new(e, false)),
+                Err(_) => Err(Failure {
+                    retry_after,
+                    ..Failure::new(status_error, status == StatusCode::TOO_MANY_REQUESTS)
+                }),
+            }
+        }
+        _ => Err(Failure {
+            retry_after: retry_after(res.headers()),
+            ..Failure::new(res.error_for_status().unwrap_err(), true)
+        }),
+    }
+}
+
+/// Whether `e` means the API couldn't be reached (or had a server error), so an old response is
+/// better than nothing. Client errors mean the request is wrong, like a bad key, so they aren't
+/// hidden even when their body isn't one of the API's errors.
+fn unreachable(e: &Error) -> bool {
+    match e.downcast_ref::<reqwest::Error>() {
+        Some(e) => !e.status().is_some_and(|status| status.is_client_error()),
+        None => {
+            e.downcast_ref::<DeadlinePassed>().is_some()
+                || e.downcast_ref::<BudgetReached>().is_some()
+        }
+    }
+}
+
+/// How long `Retry-After` asks to wait, whether it's in seconds or a date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let retry_after = headers.get(RETRY_AFTER)?.to_str().ok()?;
+
+    match retry_after.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => {
+            let date = DateTime::parse_from_rfc2822(retry_after).ok()?;
+            Some(
+                (date.with_timezone(&Utc) - Utc::now())
+                    .to_std()
+                    .unwrap_or_default(),
+            )
+        }
+    }
+}
+
+/// How many calls the API has counted today, when it says.
+fn api_calls(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("x-forecast-api-calls")?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+fn validity(headers: &HeaderMap) -> Validity {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+
+    Validity {
+        expires: header(EXPIRES)
+            .and_then(|expires| DateTime::parse_from_rfc2822(expires).ok())
+            .map(|expires| expires.timestamp().max(0) as u64),
+        last_modified: header(LAST_MODIFIED).map(String::from),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weather_api::darksky::{DarkSkyError, Forecast};
+
+    #[test]
+    fn it_reads_caching_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            EXPIRES,
+            HeaderValue::from_static("Tue, 13 Nov 2018 21:04:21 GMT"),
+        );
+        headers.insert(
+            LAST_MODIFIED,
+            HeaderValue::from_static("Tue, 13 Nov 2018 20:34:21 GMT"),
+        );
+
+        assert_eq!(
+            Validity {
+                expires: Some(1_542_143_061),
+                last_modified: Some(String::from("Tue, 13 Nov 2018 20:34:21 GMT")),
+            },
+            validity(&headers)
+        );
+        assert_eq!(Validity::default(), validity(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn it_reads_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(None, retry_after(&headers));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(Some(Duration::from_secs(120)), retry_after(&headers));
+
+        // Dates in the past mean now.
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Tue, 13 Nov 2018 21:04:21 GMT"),
+        );
+        assert_eq!(Some(Duration::from_secs(0)), retry_after(&headers));
+    }
+
+    #[test]
+    fn it_backs_off_exponentially_with_jitter() {
+        let retry = RetryConfig {
+            backoff: Some(1000),
+            max_backoff: Some(5000),
+            ..Default::default()
+        };
+
+        for _ in 0..20 {
+            let first = retry.backoff(0);
+            assert!(first >= Duration::from_millis(500) && first <= Duration::from_millis(1000));
+
+            let third = retry.backoff(2);
+            assert!(third >= Duration::from_millis(2000) && third <= Duration::from_millis(4000));
+
+            assert!(retry.backoff(10) <= Duration::from_millis(5000));
+        }
+        assert_eq!(3, retry.attempts());
+    }
+
+    #[test]
+    fn it_stops_fetching_after_the_deadline() {
+        let client = Client::new();
+        let fetcher = Fetcher::new(&client, None).until(Instant::now());
+        let url = Url::parse("https://api.darksky.net/forecast/my_key/12.345,-54.321").unwrap();
+
+        let e = fetcher
+            .fetch::<Forecast, DarkSkyError>(
+                Provider::DarkSky,
+                Endpoint::Current,
+                url,
+                Some("my_key"),
+                HeaderMap::new(),
+            )
+            .unwrap_err();
+
+        assert_eq!(
+            "ran out of time before fetching \
+             https://api.darksky.net/forecast/{key}/12.345,-54.321",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    fn it_doesnt_retry_after_the_deadline() {
+        // Connections are queued, but never answered.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(1))
+            .build()
+            .unwrap();
+        let retry = RetryConfig {
+            attempts: Some(5),
+            backoff: Some(0),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let fetcher = Fetcher::new(&client, None)
+            .retry(&retry)
+            .until(start + Duration::from_millis(200));
+
+        assert!(fetcher
+            .fetch::<Forecast, DarkSkyError>(
+                Provider::DarkSky,
+                Endpoint::Current,
+                url,
+                None,
+                HeaderMap::new(),
+            )
+            .is_err());
+        // The first request times out, and isn't tried again.
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    /// Answers the first request with `response`.
+    fn answer(response: &'static str) -> Url {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        thread::spawn(move || {
+            use std::io::{Read, Write};
+
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]);
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+
+        url
+    }
+
+    #[test]
+    fn it_doesnt_hide_client_errors_behind_old_responses() {
+        let client = Client::new();
+        let url = answer("HTTP/1.1 401 Unauthorized\r\nContent-Length: 9\r\n\r\nforbidden");
+        let failure = fetch_text::<DarkSkyError>(&client, Format::Json, url, HeaderMap::new())
+            .err()
+            .unwrap();
+        assert!(!unreachable(&failure.error));
+
+        let url = answer("HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n");
+        let failure = fetch_text::<DarkSkyError>(&client, Format::Json, url, HeaderMap::new())
+            .err()
+            .unwrap();
+        assert!(unreachable(&failure.error));
+        assert!(unreachable(&DeadlinePassed(String::new()).into()));
+    }
+}
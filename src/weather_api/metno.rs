@@ -0,0 +1,230 @@
+use super::Config;
+use super::{ForecastApi, Provider, WeatherApi};
+use crate::config::GenericWeatherUnit;
+use crate::weather::Report;
+use clap::ArgMatches;
+use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use url::Url;
+
+mod models;
+
+pub use self::models::{Forecast, MetNoError};
+
+/// [MET Norway's Locationforecast](https://api.met.no/weatherapi/locationforecast/2.0/documentation),
+/// which doesn't need an API key.
+///
+/// Its terms ask that the same forecast isn't fetched again before it `Expires`, which the cache
+/// takes care of.
+#[derive(Debug, PartialEq)]
+pub struct MetNo<'a> {
+    pub user_agent: &'a str,
+    pub coordinates: (f64, f64),
+    pub unit: GenericWeatherUnit,
+    /// Use the `complete` forecast instead of `compact`.
+    pub complete: bool,
+}
+
+impl<'a> WeatherApi<'a> for MetNo<'a> {
+    const BASE_URL: &'static str = "https://api.met.no/weatherapi/locationforecast/2.0";
+    const PROVIDER: Provider = Provider::MetNo;
+    type Current = Forecast;
+    type ApiError = MetNoError;
+
+    fn new(config: &'a Config, m: &'a ArgMatches) -> Self {
+        let metno_matches = m.subcommand_matches("metno");
+        let metno_config = config.metno.as_ref();
+
+        let coordinates = values_t!(m.values_of("coordinates"), f64)
+            .map(|coordinates| (coordinates[0], coordinates[1]))
+            .ok()
+            .or(config.coordinates)
+            .expect("tried creating met.no api without coordinates in config");
+
+        let unit = metno_matches
+            .and_then(|metno| value_t!(metno.value_of("units"), GenericWeatherUnit).ok())
+            .or_else(|| value_t!(m.value_of("units"), GenericWeatherUnit).ok())
+            .or_else(|| metno_config.and_then(|metno| metno.unit))
+            .or(config.unit)
+            .unwrap_or(GenericWeatherUnit::Metric);
+
+        let complete = metno_matches.is_some_and(|metno| metno.is_present("complete"))
+            || metno_config.and_then(|metno| metno.complete) == Some(true);
+
+        Self {
+            user_agent: metno_config
+                .and_then(|metno| metno.user_agent.as_ref())
+                .map_or(
+                    concat!(crate_name!(), "/", crate_version!()),
+                    String::as_str,
+                ),
+            coordinates,
+            unit,
+            complete,
+        }
+    }
+
+    fn url(&self) -> Url {
+        let mut url = Url::parse(Self::BASE_URL).unwrap();
+        url.path_segments_mut()
+            .unwrap()
+            .push(if self.complete { "complete" } else { "compact" });
+        // More precision than this is turned away.
+        url.query_pairs_mut()
+            .append_pair("lat", &format!("{:.4}", self.coordinates.0))
+            .append_pair("lon", &format!("{:.4}", self.coordinates.1))
+            .finish();
+
+        url
+    }
+
+    fn current_url(&self) -> Url {
+        self.url()
+    }
+
+    fn report(&self, current: Forecast) -> Report {
+        current.into_report(self.unit)
+    }
+
+    fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Ok(user_agent) = HeaderValue::from_str(self.user_agent) {
+            headers.insert(USER_AGENT, user_agent);
+        }
+
+        headers
+    }
+}
+
+impl<'a> ForecastApi<'a> for MetNo<'a> {
+    type Forecast = Forecast;
+
+    fn forecast_url(&self) -> Url {
+        self.url()
+    }
+
+    fn forecast_report(&self, forecast: Forecast) -> Report {
+        forecast.into_report(self.unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MetNoConfig;
+    use crate::weather::{Condition, Speed, SpeedUnit, Temperature, TemperatureUnit};
+
+    const COMPLETE: &str = r#"{
+        "type": "Feature",
+        "geometry": { "type": "Point", "coordinates": [-97.14, 49.9, 239] },
+        "properties": {
+            "meta": {
+                "updated_at": "2018-11-13T20:34:21Z",
+                "units": { "air_temperature": "celsius", "wind_speed": "m/s" }
+            },
+            "timeseries": [
+                {
+                    "time": "2018-11-13T21:00:00Z",
+                    "data": {
+                        "instant": { "details": {
+                            "air_pressure_at_sea_level": 1021.3, "air_temperature": -3.5,
+                            "cloud_area_fraction": 100, "dew_point_temperature": -7.1,
+                            "relative_humidity": 74, "wind_from_direction": 310,
+                            "wind_speed": 5, "wind_speed_of_gust": 8
+                        } },
+                        "next_1_hours": {
+                            "summary": { "symbol_code": "lightssnowshowersandthunder_night" },
+                            "details": { "precipitation_amount": 0.4, "probability_of_precipitation": 60 }
+                        },
+                        "next_6_hours": {
+                            "summary": { "symbol_code": "snow" },
+                            "details": { "air_temperature_max": -2.0, "air_temperature_min": -8.0 }
+                        }
+                    }
+                },
+                {
+                    "time": "2018-11-14T06:00:00Z",
+                    "data": {
+                        "instant": { "details": { "air_temperature": -9.5, "wind_speed": 2 } },
+                        "next_12_hours": { "summary": { "symbol_code": "partlycloudy_day" }, "details": {} }
+                    }
+                }
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn it_creates_new_metno_from_config() {
+        let config = Config {
+            coordinates: Some((49.899_54, -97.140_94)),
+            metno: Some(MetNoConfig {
+                user_agent: Some(String::from("nimbus-alt test@example.com")),
+                unit: None,
+                complete: Some(true),
+            }),
+            ..Default::default()
+        };
+
+        let matches = clap::App::new("name").get_matches_from(vec!["name"]);
+        let api = MetNo::new(&config, &matches);
+
+        assert_eq!(
+            MetNo {
+                user_agent: "nimbus-alt test@example.com",
+                coordinates: (49.899_54, -97.140_94),
+                unit: GenericWeatherUnit::Metric,
+                complete: true,
+            },
+            api
+        );
+        assert_eq!(
+            Url::parse(
+                "https://api.met.no/weatherapi/locationforecast/2.0/complete?lat=49.8995&lon=-97.1409"
+            )
+            .unwrap(),
+            api.url()
+        );
+    }
+
+    #[test]
+    fn it_normalizes_a_forecast() {
+        let forecast: Forecast = serde_json::from_str(COMPLETE).unwrap();
+        let api = MetNo {
+            user_agent: "test",
+            coordinates: (49.9, -97.14),
+            unit: GenericWeatherUnit::Metric,
+            complete: true,
+        };
+        let report = api.forecast_report(forecast);
+        let current = report.current.unwrap();
+
+        assert_eq!(SpeedUnit::MetresPerSecond, report.units.speed);
+        assert_eq!(Some(49.9), report.location.latitude);
+        assert_eq!(Condition::Thunderstorm, current.condition);
+        assert_eq!(
+            Some(String::from("Light snow showers and thunder")),
+            current.summary
+        );
+        assert_eq!(
+            Some(Speed::new(5., SpeedUnit::MetresPerSecond)),
+            current.wind_speed
+        );
+        assert_eq!(
+            Some(Temperature::new(-7.1, TemperatureUnit::Celsius)),
+            current.dew_point
+        );
+        assert_eq!(Some(0.6), current.precip_probability);
+        assert_eq!(1, report.hourly.len());
+
+        assert_eq!(2, report.daily.len());
+        assert_eq!(
+            Some(Temperature::new(-2., TemperatureUnit::Celsius)),
+            report.daily[0].temperature_high
+        );
+        assert_eq!(
+            Some(Temperature::new(-8., TemperatureUnit::Celsius)),
+            report.daily[0].temperature_low
+        );
+        assert_eq!(Condition::PartlyCloudyDay, report.daily[1].condition);
+        assert_eq!(Some(String::from("Partly cloudy")), report.daily[1].summary);
+    }
+}
@@ -0,0 +1,384 @@
+use crate::config::GenericWeatherUnit;
+use crate::weather::{
+    Condition, DailySummary, HourlyPoint, Location, Observation, PrecipitationRate,
+    PrecipitationUnit, Report, Speed, SpeedUnit, Temperature, TemperatureUnit, Unit, Units,
+};
+use crate::weather_api::Provider;
+use chrono::{DateTime, NaiveDate};
+use failure::Fail;
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// MET Norway mostly answers errors in plain text, which won't decode into this; it's here for
+/// the ones that come as JSON.
+#[derive(Fail, Debug, Deserialize)]
+pub struct MetNoError {
+    #[serde(default)]
+    status: Option<u16>,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+impl fmt::Display for MetNoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "met.no error {}: {}",
+            self.status.unwrap_or_default(),
+            self.message
+                .as_ref()
+                .map_or("unknown error", String::as_str)
+        )
+    }
+}
+
+/// A `compact` or `complete` Locationforecast, as GeoJSON.
+#[derive(Debug, Deserialize)]
+pub struct Forecast {
+    geometry: Geometry,
+    properties: Properties,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Geometry {
+    /// Longitude, latitude, and altitude in metres.
+    coordinates: Vec<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Properties {
+    #[allow(dead_code)]
+    meta: Meta,
+    timeseries: Vec<TimeStep>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Meta {
+    #[allow(dead_code)]
+    updated_at: String,
+    /// The unit of each variable, which are always SI.
+    #[allow(dead_code)]
+    units: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimeStep {
+    /// In UTC.
+    time: String,
+    data: Data,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Data {
+    instant: Instant,
+    next_1_hours: Option<Period>,
+    next_6_hours: Option<Period>,
+    next_12_hours: Option<Period>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Instant {
+    details: InstantDetails,
+}
+
+/// The `complete` forecast fills in more of these than `compact`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct InstantDetails {
+    air_pressure_at_sea_level: Option<f64>,
+    air_temperature: Option<f64>,
+    air_temperature_percentile_10: Option<f64>,
+    air_temperature_percentile_90: Option<f64>,
+    cloud_area_fraction: Option<f64>,
+    cloud_area_fraction_high: Option<f64>,
+    cloud_area_fraction_low: Option<f64>,
+    cloud_area_fraction_medium: Option<f64>,
+    dew_point_temperature: Option<f64>,
+    fog_area_fraction: Option<f64>,
+    relative_humidity: Option<f64>,
+    ultraviolet_index_clear_sky: Option<f64>,
+    wind_from_direction: Option<f64>,
+    wind_speed: Option<f64>,
+    wind_speed_of_gust: Option<f64>,
+    wind_speed_percentile_10: Option<f64>,
+    wind_speed_percentile_90: Option<f64>,
+}
+
+/// What's expected over the next 1, 6 or 12 hours.
+#[derive(Debug, Deserialize)]
+pub struct Period {
+    summary: Option<Summary>,
+    #[serde(default)]
+    details: PeriodDetails,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Summary {
+    /// e.g. `lightrainshowers_day`.
+    symbol_code: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PeriodDetails {
+    air_temperature_max: Option<f64>,
+    air_temperature_min: Option<f64>,
+    /// In mm.
+    precipitation_amount: Option<f64>,
+    precipitation_amount_max: Option<f64>,
+    precipitation_amount_min: Option<f64>,
+    probability_of_precipitation: Option<f64>,
+    probability_of_thunder: Option<f64>,
+    ultraviolet_index_clear_sky_max: Option<f64>,
+}
+
+impl Forecast {
+    /// The first time step becomes the current conditions. Days are UTC days, since that's what
+    /// the times are in.
+    pub fn into_report(self, unit: GenericWeatherUnit) -> Report {
+        let units = Units::from(unit);
+        let mut report = Report::new(Provider::MetNo, units);
+        report.location = Location {
+            latitude: self.geometry.coordinates.get(1).cloned(),
+            longitude: self.geometry.coordinates.first().cloned(),
+            ..Default::default()
+        };
+
+        let steps = self.properties.timeseries;
+        report.current = steps.first().map(|step| step.observation(units));
+        report.hourly = steps
+            .iter()
+            .filter(|step| step.data.next_1_hours.is_some())
+            .map(|step| step.hourly(units))
+            .collect();
+        report.daily = daily(&steps, units);
+
+        report
+    }
+}
+
+impl TimeStep {
+    fn time(&self) -> i64 {
+        DateTime::parse_from_rfc3339(&self.time)
+            .map(|time| time.timestamp())
+            .unwrap_or_default()
+    }
+
+    /// The shortest period ahead that has a summary.
+    fn period(&self) -> Option<&Period> {
+        let data = &self.data;
+        vec![&data.next_1_hours, &data.next_6_hours, &data.next_12_hours]
+            .into_iter()
+            .flatten()
+            .find(|period| period.summary.is_some())
+    }
+
+    fn symbol(&self) -> Option<&str> {
+        self.period()
+            .and_then(|period| period.summary.as_ref())
+            .map(|summary| summary.symbol_code.as_str())
+    }
+
+    fn hourly(&self, units: Units) -> HourlyPoint {
+        let details = &self.data.instant.details;
+        let next_hour = self
+            .data
+            .next_1_hours
+            .as_ref()
+            .map(|period| &period.details);
+
+        HourlyPoint {
+            summary: self.symbol().map(description),
+            condition: self.symbol().map_or(Condition::Unknown, condition),
+            temperature: details.air_temperature.map(|t| temperature(t, units)),
+            humidity: details.relative_humidity.map(percent),
+            pressure: units.pressure.of(details.air_pressure_at_sea_level),
+            wind_speed: details.wind_speed.map(|speed| wind_speed(speed, units)),
+            wind_bearing: details.wind_from_direction,
+            cloud_cover: details.cloud_area_fraction.map(percent),
+            precip_intensity: next_hour
+                .and_then(|next_hour| next_hour.precipitation_amount)
+                .map(|amount| precipitation(amount, units)),
+            precip_probability: next_hour
+                .and_then(|next_hour| next_hour.probability_of_precipitation)
+                .map(percent),
+            ..HourlyPoint::new(self.time())
+        }
+    }
+
+    fn observation(&self, units: Units) -> Observation {
+        let details = &self.data.instant.details;
+        let point = self.hourly(units);
+
+        Observation {
+            summary: point.summary,
+            condition: point.condition,
+            temperature: point.temperature,
+            dew_point: details.dew_point_temperature.map(|t| temperature(t, units)),
+            humidity: point.humidity,
+            pressure: point.pressure,
+            wind_speed: point.wind_speed,
+            wind_gust: details
+                .wind_speed_of_gust
+                .map(|speed| wind_speed(speed, units)),
+            wind_bearing: point.wind_bearing,
+            cloud_cover: point.cloud_cover,
+            uv_index: details.ultraviolet_index_clear_sky,
+            precip_intensity: point.precip_intensity,
+            precip_probability: point.precip_probability,
+            ..Observation::new(point.time)
+        }
+    }
+}
+
+fn daily(steps: &[TimeStep], units: Units) -> Vec<DailySummary> {
+    let mut days: BTreeMap<NaiveDate, Vec<&TimeStep>> = BTreeMap::new();
+    for step in steps {
+        if let Ok(time) = DateTime::parse_from_rfc3339(&step.time) {
+            days.entry(time.date_naive()).or_default().push(step);
+        }
+    }
+
+    days.into_iter()
+        .map(|(date, steps)| {
+            let periods = || steps.iter().filter_map(|step| step.period());
+            // Only the 6 hour periods have highs and lows.
+            let six_hours = || {
+                steps
+                    .iter()
+                    .filter_map(|step| step.data.next_6_hours.as_ref())
+            };
+            let instants = || {
+                steps
+                    .iter()
+                    .filter_map(|step| step.data.instant.details.air_temperature)
+            };
+            let highs = instants()
+                .chain(six_hours().filter_map(|period| period.details.air_temperature_max));
+            let lows = instants()
+                .chain(six_hours().filter_map(|period| period.details.air_temperature_min));
+            // The forecast for the daytime, if it goes back that far.
+            let symbol = steps
+                .iter()
+                .find(|step| step.time.contains("T06:00"))
+                .or_else(|| steps.first())
+                .and_then(|step| step.symbol());
+
+            DailySummary {
+                summary: symbol.map(description),
+                condition: symbol.map_or(Condition::Unknown, condition),
+                temperature_high: highs.reduce(f64::max).map(|t| temperature(t, units)),
+                temperature_low: lows.reduce(f64::min).map(|t| temperature(t, units)),
+                wind_speed: steps
+                    .iter()
+                    .filter_map(|step| step.data.instant.details.wind_speed)
+                    .reduce(f64::max)
+                    .map(|speed| wind_speed(speed, units)),
+                precip_probability: periods()
+                    .filter_map(|period| period.details.probability_of_precipitation)
+                    .reduce(f64::max)
+                    .map(percent),
+                uv_index: periods()
+                    .filter_map(|period| period.details.ultraviolet_index_clear_sky_max)
+                    .reduce(f64::max),
+                ..DailySummary::new(
+                    date.and_hms_opt(0, 0, 0)
+                        .map(|midnight| midnight.and_utc().timestamp())
+                        .unwrap_or_default(),
+                )
+            }
+        })
+        .collect()
+}
+
+fn percent(value: f64) -> f64 {
+    value / 100.
+}
+
+/// Everything comes in SI units.
+fn temperature(celsius: f64, units: Units) -> Temperature {
+    Temperature::new(celsius, TemperatureUnit::Celsius).to(units.temperature)
+}
+
+fn wind_speed(metres_per_second: f64, units: Units) -> Speed {
+    Speed::new(metres_per_second, SpeedUnit::MetresPerSecond).to(units.speed)
+}
+
+/// The amount over the next hour, which is the same as the rate.
+fn precipitation(mm: f64, units: Units) -> PrecipitationRate {
+    PrecipitationRate::new(mm, PrecipitationUnit::MillimetresPerHour).to(units.precipitation)
+}
+
+/// Split a symbol code like `rainshowers_polartwilight` into the weather and whether it's night.
+fn symbol_parts(symbol: &str) -> (&str, bool) {
+    match symbol.rfind('_') {
+        Some(i) => (&symbol[..i], &symbol[i + 1..] == "night"),
+        None => (symbol, false),
+    }
+}
+
+/// Map a MET symbol code onto a [`Condition`].
+///
+/// See <https://api.met.no/weatherapi/weathericon/2.0/documentation>.
+fn condition(symbol: &str) -> Condition {
+    let (weather, night) = symbol_parts(symbol);
+
+    match weather {
+        "clearsky" | "fair" if night => Condition::ClearNight,
+        "clearsky" | "fair" => Condition::ClearDay,
+        "partlycloudy" if night => Condition::PartlyCloudyNight,
+        "partlycloudy" => Condition::PartlyCloudyDay,
+        "cloudy" => Condition::Cloudy,
+        "fog" => Condition::Fog,
+        weather if weather.ends_with("andthunder") => Condition::Thunderstorm,
+        weather if weather.contains("sleet") => Condition::Sleet,
+        weather if weather.contains("snow") => Condition::Snow,
+        weather if weather.contains("rain") => Condition::Rain,
+        _ => Condition::Unknown,
+    }
+}
+
+/// Spell out a symbol code, e.g. `heavyrainshowersandthunder_day` as "Heavy rain showers and
+/// thunder".
+fn description(symbol: &str) -> String {
+    let (weather, _) = symbol_parts(symbol);
+    let mut words = Vec::new();
+
+    let weather = match weather {
+        "clearsky" => return String::from("Clear sky"),
+        "partlycloudy" => return String::from("Partly cloudy"),
+        weather => weather,
+    };
+    let (weather, thunder) = match weather.strip_suffix("andthunder") {
+        Some(weather) => (weather, true),
+        None => (weather, false),
+    };
+    let weather = if let Some(weather) = weather.strip_prefix("light") {
+        words.push("light");
+        // A couple of codes are spelled `lights...`.
+        weather
+            .strip_prefix('s')
+            .filter(|weather| weather.starts_with('s'))
+            .unwrap_or(weather)
+    } else if let Some(weather) = weather.strip_prefix("heavy") {
+        words.push("heavy");
+        weather
+    } else {
+        weather
+    };
+    match weather.strip_suffix("showers") {
+        Some(weather) => words.extend(&[weather, "showers"]),
+        None => words.push(weather),
+    }
+    if thunder {
+        words.push("and thunder");
+    }
+
+    let description = words.join(" ");
+    let mut chars = description.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,388 @@
+use super::Config;
+use super::{join, Fetched, Fetcher, ForecastApi, Provider, WeatherApi};
+use crate::cache::Endpoint;
+use crate::config::GenericWeatherUnit;
+use crate::weather::Report;
+use clap::ArgMatches;
+use failure::Error;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, USER_AGENT};
+use std::thread;
+use url::Url;
+
+mod models;
+
+pub use self::models::{Alerts, Bundle, GridForecast, NwsError, Point};
+
+/// The [National Weather Service](https://www.weather.gov/documentation/services-web-api), which
+/// only covers the US.
+///
+/// A location is looked up in two steps: `/points` says which grid covers it, and the grid has the
+/// forecasts.
+#[derive(Debug, PartialEq)]
+pub struct Nws<'a> {
+    pub user_agent: &'a str,
+    pub coordinates: (f64, f64),
+    pub unit: GenericWeatherUnit,
+}
+
+impl<'a> Nws<'a> {
+    fn alerts_url(&self) -> Url {
+        let mut url = Url::parse(Self::BASE_URL).unwrap();
+        url.path_segments_mut()
+            .unwrap()
+            .extend(&["alerts", "active"]);
+        url.query_pairs_mut()
+            .append_pair(
+                "point",
+                &format!("{:.4},{:.4}", self.coordinates.0, self.coordinates.1),
+            )
+            .finish();
+
+        url
+    }
+
+    /// A grid forecast URL from `/points`, in the units asked for.
+    fn grid_url(&self, url: &str) -> Result<Url, Error> {
+        let mut url = Url::parse(url)?;
+        url.query_pairs_mut()
+            .append_pair(
+                "units",
+                match self.unit {
+                    GenericWeatherUnit::Metric => "si",
+                    GenericWeatherUnit::Imperial => "us",
+                },
+            )
+            .finish();
+
+        Ok(url)
+    }
+
+    fn fetch<T: serde::de::DeserializeOwned>(
+        &self,
+        fetcher: &Fetcher,
+        endpoint: Endpoint,
+        url: Url,
+    ) -> Result<Fetched<T>, Error> {
+        fetcher.fetch::<T, NwsError>(Self::PROVIDER, endpoint, url, None, self.headers())
+    }
+
+    /// Look up the grid, then fetch the hourly forecast (and the 12 hour one, with `forecast`)
+    /// and the active alerts.
+    fn fetch_bundle(&self, fetcher: &Fetcher, forecast: bool) -> Result<Fetched<Bundle>, Error> {
+        // Which grid covers a location hardly ever changes, so it's cached like historical data.
+        let point: Fetched<Point> =
+            self.fetch(fetcher, Endpoint::Historical, self.current_url())?;
+
+        // The rest only depend on the point, so they're fetched at the same time.
+        let (hourly, alerts, daily) = thread::scope(|scope| {
+            let alerts =
+                scope.spawn(|| self.fetch::<Alerts>(fetcher, Endpoint::Current, self.alerts_url()));
+            let daily = scope.spawn(|| {
+                if forecast {
+                    let url = self.grid_url(point.value.forecast_url())?;
+                    self.fetch::<GridForecast>(fetcher, Endpoint::Forecast, url)
+                        .map(Some)
+                } else {
+                    Ok(None)
+                }
+            });
+            let hourly = self
+                .grid_url(point.value.forecast_hourly_url())
+                .and_then(|url| self.fetch::<GridForecast>(fetcher, Endpoint::Current, url));
+
+            (hourly, join(alerts), join(daily))
+        });
+        let (hourly, alerts, daily) = (hourly?, alerts?, daily?);
+
+        let fetched = point.and(hourly).and(alerts);
+        Ok(match daily {
+            Some(daily) => fetched
+                .and(daily)
+                .map(|(((point, hourly), alerts), daily)| (point, hourly, alerts, Some(daily))),
+            None => fetched.map(|((point, hourly), alerts)| (point, hourly, alerts, None)),
+        }
+        .map(|(point, hourly, alerts, forecast)| Bundle {
+            point,
+            forecast,
+            hourly,
+            alerts,
+        }))
+    }
+}
+
+impl<'a> WeatherApi<'a> for Nws<'a> {
+    const BASE_URL: &'static str = "https://api.weather.gov";
+    const PROVIDER: Provider = Provider::Nws;
+    type Current = Bundle;
+    type ApiError = NwsError;
+
+    fn new(config: &'a Config, m: &'a ArgMatches) -> Self {
+        let nws_matches = m.subcommand_matches("nws");
+        let nws_config = config
+            .nws
+            .as_ref()
+            .expect("Tried to create Nws without a user agent.");
+
+        let coordinates = values_t!(m.values_of("coordinates"), f64)
+            .map(|coordinates| (coordinates[0], coordinates[1]))
+            .ok()
+            .or(config.coordinates)
+            .expect("tried creating nws api without coordinates in config");
+
+        let unit = nws_matches
+            .and_then(|nws| value_t!(nws.value_of("units"), GenericWeatherUnit).ok())
+            .or_else(|| value_t!(m.value_of("units"), GenericWeatherUnit).ok())
+            .or(nws_config.unit)
+            .or(config.unit)
+            // The NWS answers in US units unless it's told otherwise.
+            .unwrap_or(GenericWeatherUnit::Imperial);
+
+        Self {
+            user_agent: &nws_config.user_agent,
+            coordinates,
+            unit,
+        }
+    }
+
+    fn url(&self) -> Url {
+        let mut url = Url::parse(Self::BASE_URL).unwrap();
+        url.path_segments_mut().unwrap().extend(&[
+            "points",
+            &format!("{:.4},{:.4}", self.coordinates.0, self.coordinates.1),
+        ]);
+
+        url
+    }
+
+    fn current_url(&self) -> Url {
+        self.url()
+    }
+
+    fn report(&self, current: Bundle) -> Report {
+        current.into_report(self.unit)
+    }
+
+    fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Ok(user_agent) = HeaderValue::from_str(self.user_agent) {
+            headers.insert(USER_AGENT, user_agent);
+        }
+        headers.insert(ACCEPT, HeaderValue::from_static("application/geo+json"));
+
+        headers
+    }
+
+    fn fetch_current(&self, fetcher: &Fetcher) -> Result<Fetched<Bundle>, Error> {
+        self.fetch_bundle(fetcher, false)
+    }
+}
+
+impl<'a> ForecastApi<'a> for Nws<'a> {
+    type Forecast = Bundle;
+
+    /// Where the forecasts are found. The grid's own forecast URLs come from here.
+    fn forecast_url(&self) -> Url {
+        self.url()
+    }
+
+    fn forecast_report(&self, forecast: Bundle) -> Report {
+        forecast.into_report(self.unit)
+    }
+
+    fn fetch_forecast(&self, fetcher: &Fetcher) -> Result<Fetched<Bundle>, Error> {
+        self.fetch_bundle(fetcher, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NwsConfig;
+    use crate::weather::{Condition, Severity, Speed, SpeedUnit, Temperature, TemperatureUnit};
+
+    fn api() -> Nws<'static> {
+        Nws {
+            user_agent: "(nimbus-alt, test@example.com)",
+            coordinates: (39.7456, -97.0892),
+            unit: GenericWeatherUnit::Imperial,
+        }
+    }
+
+    #[test]
+    fn it_creates_new_nws_with_a_user_agent() {
+        let config = Config {
+            coordinates: Some((39.745_63, -97.089_21)),
+            unit: Some(GenericWeatherUnit::Metric),
+            nws: Some(NwsConfig {
+                user_agent: String::from("(nimbus-alt, test@example.com)"),
+                unit: Some(GenericWeatherUnit::Imperial),
+            }),
+            ..Default::default()
+        };
+
+        let matches = clap::App::new("name").get_matches_from(vec!["name"]);
+        let api = Nws::new(&config, &matches);
+
+        assert_eq!(GenericWeatherUnit::Imperial, api.unit);
+        assert_eq!(
+            Url::parse("https://api.weather.gov/points/39.7456,-97.0892").unwrap(),
+            api.url()
+        );
+        assert_eq!(
+            Url::parse("https://api.weather.gov/alerts/active?point=39.7456%2C-97.0892").unwrap(),
+            api.alerts_url()
+        );
+        assert_eq!(
+            "(nimbus-alt, test@example.com)",
+            api.headers()[USER_AGENT].to_str().unwrap()
+        );
+    }
+
+    #[test]
+    fn it_asks_the_grid_for_units() {
+        let point: Point = serde_json::from_str(
+            r#"{
+                "@context": [],
+                "id": "https://api.weather.gov/points/39.7456,-97.0892",
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": [-97.0892, 39.7456] },
+                "properties": {
+                    "gridId": "TOP",
+                    "gridX": 32,
+                    "gridY": 81,
+                    "forecast": "https://api.weather.gov/gridpoints/TOP/32,81/forecast",
+                    "forecastHourly": "https://api.weather.gov/gridpoints/TOP/32,81/forecast/hourly",
+                    "timeZone": "America/Chicago"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Url::parse("https://api.weather.gov/gridpoints/TOP/32,81/forecast/hourly?units=us")
+                .unwrap(),
+            api().grid_url(point.forecast_hourly_url()).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_normalizes_a_bundle() {
+        let bundle = Bundle {
+            point: serde_json::from_str(
+                r#"{
+                    "properties": {
+                        "gridId": "TOP", "gridX": 32, "gridY": 81,
+                        "forecast": "https://api.weather.gov/gridpoints/TOP/32,81/forecast",
+                        "forecastHourly": "https://api.weather.gov/gridpoints/TOP/32,81/forecast/hourly",
+                        "relativeLocation": {
+                            "type": "Feature",
+                            "properties": { "city": "Linn", "state": "KS" }
+                        },
+                        "timeZone": "America/Chicago"
+                    }
+                }"#,
+            )
+            .unwrap(),
+            forecast: Some(
+                serde_json::from_str(
+                    r#"{
+                        "properties": {
+                            "units": "us",
+                            "periods": [
+                                {
+                                    "number": 1, "name": "Today",
+                                    "startTime": "2018-11-13T06:00:00-06:00",
+                                    "endTime": "2018-11-13T18:00:00-06:00",
+                                    "isDaytime": true, "temperature": 50, "temperatureUnit": "F",
+                                    "probabilityOfPrecipitation": { "unitCode": "wmoUnit:percent", "value": 20 },
+                                    "windSpeed": "5 to 10 mph", "windDirection": "NW",
+                                    "icon": "https://api.weather.gov/icons/land/day/sct?size=medium",
+                                    "shortForecast": "Partly Sunny"
+                                },
+                                {
+                                    "number": 2, "name": "Tonight",
+                                    "startTime": "2018-11-13T18:00:00-06:00",
+                                    "endTime": "2018-11-14T06:00:00-06:00",
+                                    "isDaytime": false, "temperature": 30, "temperatureUnit": "F",
+                                    "probabilityOfPrecipitation": { "unitCode": "wmoUnit:percent", "value": 40 },
+                                    "windSpeed": "5 mph", "windDirection": "N",
+                                    "icon": "https://api.weather.gov/icons/land/night/snow,40?size=medium",
+                                    "shortForecast": "Chance Snow"
+                                }
+                            ]
+                        }
+                    }"#,
+                )
+                .unwrap(),
+            ),
+            hourly: serde_json::from_str(
+                r#"{
+                    "properties": {
+                        "periods": [{
+                            "number": 1,
+                            "startTime": "2018-11-13T15:00:00-06:00",
+                            "endTime": "2018-11-13T16:00:00-06:00",
+                            "isDaytime": true, "temperature": 47, "temperatureUnit": "F",
+                            "dewpoint": { "unitCode": "wmoUnit:degC", "value": -5 },
+                            "relativeHumidity": { "unitCode": "wmoUnit:percent", "value": 45 },
+                            "windSpeed": "10 mph", "windDirection": "WNW",
+                            "icon": "https://api.weather.gov/icons/land/day/rain_showers,30/tsra,60?size=small",
+                            "shortForecast": "Showers"
+                        }]
+                    }
+                }"#,
+            )
+            .unwrap(),
+            alerts: serde_json::from_str(
+                r#"{
+                    "features": [{
+                        "properties": {
+                            "id": "urn:oid:2.49.0.1.840.0.1",
+                            "areaDesc": "Washington; Marshall",
+                            "sent": "2018-11-13T14:00:00-06:00",
+                            "ends": "2018-11-14T06:00:00-06:00",
+                            "event": "Winter Storm Watch",
+                            "headline": "Winter Storm Watch issued November 13",
+                            "description": "Heavy snow possible."
+                        }
+                    }]
+                }"#,
+            )
+            .unwrap(),
+        };
+
+        let report = api().forecast_report(bundle);
+        let current = report.current.unwrap();
+
+        assert_eq!(Some(String::from("Linn, KS")), report.location.name);
+        assert_eq!(TemperatureUnit::Fahrenheit, report.units.temperature);
+        assert_eq!(1_542_142_800, current.time);
+        assert_eq!(Condition::Rain, current.condition);
+        assert_eq!(
+            Some(Temperature::new(23., TemperatureUnit::Fahrenheit)),
+            current.dew_point
+        );
+        assert_eq!(Some(0.45), current.humidity);
+        assert_eq!(Some(292.5), current.wind_bearing);
+
+        assert_eq!(1, report.daily.len());
+        assert_eq!(Condition::PartlyCloudyDay, report.daily[0].condition);
+        assert_eq!(
+            Some(Temperature::new(50., TemperatureUnit::Fahrenheit)),
+            report.daily[0].temperature_high
+        );
+        assert_eq!(
+            Some(Temperature::new(30., TemperatureUnit::Fahrenheit)),
+            report.daily[0].temperature_low
+        );
+        assert_eq!(
+            Some(Speed::new(7.5, SpeedUnit::MilesPerHour)),
+            report.daily[0].wind_speed
+        );
+        assert_eq!(Some(0.4), report.daily[0].precip_probability);
+
+        assert_eq!(Some(Severity::Watch), report.alerts[0].severity);
+        assert_eq!(vec!["Washington", "Marshall"], report.alerts[0].regions);
+        assert_eq!(Some(1_542_196_800), report.alerts[0].expires);
+    }
+}
@@ -0,0 +1,453 @@
+use crate::config::GenericWeatherUnit;
+use crate::weather::{
+    self, Condition, DailySummary, HourlyPoint, Location, Observation, Report, Speed, SpeedUnit,
+    Temperature, TemperatureUnit, Units,
+};
+use crate::weather_api::Provider;
+use chrono::DateTime;
+use failure::Fail;
+use serde_derive::Deserialize;
+use std::fmt;
+
+/// An [RFC 7807](https://tools.ietf.org/html/rfc7807) problem, which is how the NWS reports
+/// errors.
+#[derive(Fail, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NwsError {
+    status: Option<u16>,
+    title: String,
+    detail: Option<String>,
+    correlation_id: Option<String>,
+}
+
+impl fmt::Display for NwsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "nws error {}: {}",
+            self.status.unwrap_or_default(),
+            self.title
+        )?;
+        match &self.detail {
+            Some(detail) => write!(f, " ({})", detail),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `/points/{lat},{lon}`, which says which grid and forecast office cover a location.
+#[derive(Debug, Deserialize)]
+pub struct Point {
+    properties: PointProperties,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointProperties {
+    #[allow(dead_code)]
+    grid_id: String,
+    #[allow(dead_code)]
+    grid_x: i32,
+    #[allow(dead_code)]
+    grid_y: i32,
+    forecast: String,
+    forecast_hourly: String,
+    #[allow(dead_code)]
+    forecast_grid_data: Option<String>,
+    #[allow(dead_code)]
+    observation_stations: Option<String>,
+    relative_location: Option<RelativeLocation>,
+    time_zone: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RelativeLocation {
+    properties: RelativeLocationProperties,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RelativeLocationProperties {
+    city: Option<String>,
+    state: Option<String>,
+}
+
+/// `/gridpoints/{office}/{x},{y}/forecast` (12 hour periods) or `.../forecast/hourly`.
+#[derive(Debug, Deserialize)]
+pub struct GridForecast {
+    properties: GridForecastProperties,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GridForecastProperties {
+    #[allow(dead_code)]
+    updated: Option<String>,
+    /// `us` or `si`.
+    #[allow(dead_code)]
+    units: Option<String>,
+    #[allow(dead_code)]
+    generated_at: Option<String>,
+    #[allow(dead_code)]
+    update_time: Option<String>,
+    #[allow(dead_code)]
+    valid_times: Option<String>,
+    #[allow(dead_code)]
+    elevation: Option<QuantitativeValue>,
+    #[serde(default)]
+    periods: Vec<Period>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Period {
+    #[allow(dead_code)]
+    number: u32,
+    #[allow(dead_code)]
+    name: Option<String>,
+    start_time: String,
+    #[allow(dead_code)]
+    end_time: Option<String>,
+    is_daytime: bool,
+    temperature: Option<f64>,
+    /// `F` or `C`.
+    temperature_unit: Option<String>,
+    #[allow(dead_code)]
+    temperature_trend: Option<String>,
+    probability_of_precipitation: Option<QuantitativeValue>,
+    dewpoint: Option<QuantitativeValue>,
+    relative_humidity: Option<QuantitativeValue>,
+    /// e.g. `10 mph` or `5 to 10 mph`.
+    wind_speed: Option<String>,
+    /// A compass direction, e.g. `NW`.
+    wind_direction: Option<String>,
+    /// e.g. `https://api.weather.gov/icons/land/night/rain_showers,40?size=medium`.
+    icon: Option<String>,
+    short_forecast: Option<String>,
+    #[allow(dead_code)]
+    detailed_forecast: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuantitativeValue {
+    value: Option<f64>,
+    /// e.g. `wmoUnit:degC` or `wmoUnit:percent`.
+    unit_code: Option<String>,
+}
+
+/// `/alerts/active?point={lat},{lon}`.
+#[derive(Debug, Deserialize)]
+pub struct Alerts {
+    #[serde(default)]
+    features: Vec<AlertFeature>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AlertFeature {
+    properties: AlertProperties,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertProperties {
+    id: Option<String>,
+    area_desc: Option<String>,
+    sent: Option<String>,
+    effective: Option<String>,
+    #[allow(dead_code)]
+    onset: Option<String>,
+    expires: Option<String>,
+    ends: Option<String>,
+    #[allow(dead_code)]
+    status: Option<String>,
+    #[allow(dead_code)]
+    message_type: Option<String>,
+    #[allow(dead_code)]
+    category: Option<String>,
+    #[allow(dead_code)]
+    severity: Option<String>,
+    #[allow(dead_code)]
+    certainty: Option<String>,
+    #[allow(dead_code)]
+    urgency: Option<String>,
+    event: String,
+    #[allow(dead_code)]
+    sender_name: Option<String>,
+    headline: Option<String>,
+    description: Option<String>,
+    #[allow(dead_code)]
+    instruction: Option<String>,
+    #[allow(dead_code)]
+    response: Option<String>,
+}
+
+/// Everything the points flow fetched for a location.
+#[derive(Debug, Deserialize)]
+pub struct Bundle {
+    pub(super) point: Point,
+    /// The 12 hour forecast, when a forecast was asked for.
+    pub(super) forecast: Option<GridForecast>,
+    pub(super) hourly: GridForecast,
+    pub(super) alerts: Alerts,
+}
+
+impl Point {
+    pub fn forecast_url(&self) -> &str {
+        &self.properties.forecast
+    }
+
+    pub fn forecast_hourly_url(&self) -> &str {
+        &self.properties.forecast_hourly
+    }
+}
+
+impl Bundle {
+    /// The hour that's underway becomes the current conditions.
+    pub fn into_report(self, unit: GenericWeatherUnit) -> Report {
+        let units = Units::from(unit);
+        let mut report = Report::new(Provider::Nws, units);
+        let properties = self.point.properties;
+        let place = properties
+            .relative_location
+            .map(|location| location.properties);
+
+        report.location = Location {
+            name: place
+                .as_ref()
+                .and_then(|place| match (&place.city, &place.state) {
+                    (Some(city), Some(state)) => Some(format!("{}, {}", city, state)),
+                    (city, _) => city.clone(),
+                }),
+            country: Some(String::from("US")),
+            timezone: properties.time_zone,
+            ..Default::default()
+        };
+
+        let periods = self.hourly.properties.periods;
+        report.current = periods.first().map(|period| period.observation(units));
+        report.hourly = periods.iter().map(|period| period.hourly(units)).collect();
+        report.daily = self
+            .forecast
+            .map(|forecast| daily(&forecast.properties.periods, units))
+            .unwrap_or_default();
+        report.alerts = self
+            .alerts
+            .features
+            .into_iter()
+            .map(|feature| feature.properties.into_alert())
+            .collect();
+
+        report
+    }
+}
+
+impl Period {
+    fn time(&self) -> i64 {
+        timestamp(&self.start_time).unwrap_or_default()
+    }
+
+    fn temperature(&self, units: Units) -> Option<Temperature> {
+        let celsius = self
+            .temperature_unit
+            .as_ref()
+            .is_some_and(|unit| unit == "C");
+        self.temperature
+            .map(|value| temperature(value, celsius, units))
+    }
+
+    fn hourly(&self, units: Units) -> HourlyPoint {
+        HourlyPoint {
+            summary: self.short_forecast.clone(),
+            condition: self.condition(),
+            temperature: self.temperature(units),
+            humidity: percent(&self.relative_humidity),
+            wind_speed: self
+                .wind_speed
+                .as_ref()
+                .and_then(|speed| wind_speed(speed, units)),
+            wind_bearing: self.wind_direction.as_ref().and_then(|dir| bearing(dir)),
+            precip_probability: percent(&self.probability_of_precipitation),
+            ..HourlyPoint::new(self.time())
+        }
+    }
+
+    fn observation(&self, units: Units) -> Observation {
+        let point = self.hourly(units);
+
+        Observation {
+            summary: point.summary,
+            condition: point.condition,
+            temperature: point.temperature,
+            dew_point: self.dewpoint.as_ref().and_then(|dew_point| {
+                let celsius = dew_point
+                    .unit_code
+                    .as_ref()
+                    .is_some_and(|code| code.ends_with("degC"));
+                dew_point
+                    .value
+                    .map(|value| temperature(value, celsius, units))
+            }),
+            humidity: point.humidity,
+            wind_speed: point.wind_speed,
+            wind_bearing: point.wind_bearing,
+            precip_probability: point.precip_probability,
+            ..Observation::new(point.time)
+        }
+    }
+
+    /// Map the icon's name onto a [`Condition`].
+    ///
+    /// See <https://api.weather.gov/icons>.
+    fn condition(&self) -> Condition {
+        let icon = match &self.icon {
+            Some(icon) => icon,
+            None => return Condition::Unknown,
+        };
+        // `.../icons/land/{day|night}/{name}[,{pop}][/{name}[,{pop}]]?size=...`
+        let path = icon.split('?').next().unwrap_or_default();
+        let name = path
+            .split('/')
+            .skip_while(|segment| *segment != "day" && *segment != "night")
+            .nth(1)
+            .and_then(|name| name.split(',').next())
+            .unwrap_or_default();
+
+        match name {
+            "skc" | "few" | "hot" | "cold" if self.is_daytime => Condition::ClearDay,
+            "skc" | "few" | "hot" | "cold" => Condition::ClearNight,
+            "sct" | "bkn" if self.is_daytime => Condition::PartlyCloudyDay,
+            "sct" | "bkn" => Condition::PartlyCloudyNight,
+            "ovc" => Condition::Cloudy,
+            "wind_skc" | "wind_few" | "wind_sct" | "wind_bkn" | "wind_ovc" | "tornado"
+            | "hurricane" | "tropical_storm" => Condition::Wind,
+            "snow" | "blizzard" => Condition::Snow,
+            "rain_snow" | "rain_sleet" | "snow_sleet" | "fzra" | "rain_fzra" | "snow_fzra"
+            | "sleet" => Condition::Sleet,
+            "rain" | "rain_showers" | "rain_showers_hi" => Condition::Rain,
+            "tsra" | "tsra_sct" | "tsra_hi" => Condition::Thunderstorm,
+            "fog" | "haze" | "smoke" | "dust" => Condition::Fog,
+            _ => Condition::Unknown,
+        }
+    }
+}
+
+impl AlertProperties {
+    fn into_alert(self) -> weather::Alert {
+        let severity = if self.event.ends_with("Warning") {
+            Some(weather::Severity::Warning)
+        } else if self.event.ends_with("Watch") {
+            Some(weather::Severity::Watch)
+        } else if self.event.ends_with("Advisory") {
+            Some(weather::Severity::Advisory)
+        } else {
+            None
+        };
+
+        weather::Alert {
+            title: self.headline.unwrap_or(self.event),
+            description: self.description.unwrap_or_default(),
+            severity,
+            regions: self
+                .area_desc
+                .map(|areas| areas.split("; ").map(String::from).collect())
+                .unwrap_or_default(),
+            issued: self
+                .sent
+                .or(self.effective)
+                .and_then(|sent| timestamp(&sent))
+                .unwrap_or_default(),
+            expires: self.ends.or(self.expires).and_then(|ends| timestamp(&ends)),
+            uri: self.id,
+        }
+    }
+}
+
+/// Pair each daytime period with the night after it.
+fn daily(periods: &[Period], units: Units) -> Vec<DailySummary> {
+    let mut days: Vec<DailySummary> = Vec::new();
+    let mut previous_was_day = false;
+
+    for period in periods {
+        let temperature = period.temperature(units);
+        let chance = percent(&period.probability_of_precipitation);
+
+        match days.last_mut() {
+            Some(day) if previous_was_day && !period.is_daytime => {
+                day.temperature_low = temperature;
+                day.precip_probability = match (day.precip_probability, chance) {
+                    (Some(day), Some(night)) => Some(day.max(night)),
+                    (day, night) => day.or(night),
+                };
+            }
+            _ => days.push(DailySummary {
+                summary: period.short_forecast.clone(),
+                condition: period.condition(),
+                temperature_high: temperature.filter(|_| period.is_daytime),
+                temperature_low: temperature.filter(|_| !period.is_daytime),
+                wind_speed: period
+                    .wind_speed
+                    .as_ref()
+                    .and_then(|speed| wind_speed(speed, units)),
+                wind_bearing: period.wind_direction.as_ref().and_then(|dir| bearing(dir)),
+                precip_probability: chance,
+                ..DailySummary::new(period.time())
+            }),
+        }
+        previous_was_day = period.is_daytime;
+    }
+
+    days
+}
+
+fn timestamp(time: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|time| time.timestamp())
+}
+
+fn percent(value: &Option<QuantitativeValue>) -> Option<f64> {
+    value
+        .as_ref()
+        .and_then(|value| value.value)
+        .map(|value| value / 100.)
+}
+
+fn temperature(value: f64, celsius: bool, units: Units) -> Temperature {
+    let unit = if celsius {
+        TemperatureUnit::Celsius
+    } else {
+        TemperatureUnit::Fahrenheit
+    };
+
+    Temperature::new(value, unit).to(units.temperature)
+}
+
+/// `10 mph` or `5 to 10 mph`, as the middle of the range.
+fn wind_speed(speed: &str, units: Units) -> Option<Speed> {
+    let numbers: Vec<f64> = speed
+        .split_whitespace()
+        .filter_map(|word| word.parse().ok())
+        .collect();
+    let unit = if speed.ends_with("km/h") {
+        SpeedUnit::KilometresPerHour
+    } else {
+        SpeedUnit::MilesPerHour
+    };
+
+    match numbers.len() {
+        0 => None,
+        n => Some(Speed::new(numbers.iter().sum::<f64>() / n as f64, unit).to(units.speed)),
+    }
+}
+
+fn bearing(direction: &str) -> Option<f64> {
+    const POINTS: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW",
+        "NW", "NNW",
+    ];
+
+    POINTS
+        .iter()
+        .position(|point| *point == direction)
+        .map(|i| i as f64 * 22.5)
+}
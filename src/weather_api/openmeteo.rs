@@ -0,0 +1,298 @@
+use super::Config;
+use super::{ForecastApi, HistoricalApi, Provider, WeatherApi};
+use crate::config::GenericWeatherUnit;
+use crate::weather::Report;
+use chrono::{Duration, TimeZone, Utc};
+use clap::ArgMatches;
+use url::Url;
+
+mod models;
+
+pub use self::models::{Forecast, OpenMeteoError};
+
+const CURRENT: &str = "temperature_2m,relative_humidity_2m,apparent_temperature,dew_point_2m,\
+                       is_day,precipitation,weather_code,cloud_cover,pressure_msl,\
+                       wind_speed_10m,wind_direction_10m,wind_gusts_10m,visibility,uv_index";
+const HOURLY: &str = "temperature_2m,relative_humidity_2m,apparent_temperature,\
+                      precipitation_probability,precipitation,weather_code,pressure_msl,\
+                      cloud_cover,wind_speed_10m,wind_direction_10m,is_day";
+const DAILY: &str = "weather_code,temperature_2m_max,temperature_2m_min,\
+                     apparent_temperature_max,apparent_temperature_min,sunrise,sunset,\
+                     uv_index_max,precipitation_probability_max,wind_speed_10m_max,\
+                     wind_direction_10m_dominant";
+// The archive doesn't have probabilities or UV indexes.
+const ARCHIVE_HOURLY: &str = "temperature_2m,relative_humidity_2m,apparent_temperature,\
+                              dew_point_2m,precipitation,weather_code,pressure_msl,cloud_cover,\
+                              wind_speed_10m,wind_direction_10m,wind_gusts_10m,is_day";
+const ARCHIVE_DAILY: &str = "weather_code,temperature_2m_max,temperature_2m_min,\
+                             apparent_temperature_max,apparent_temperature_min,sunrise,sunset,\
+                             wind_speed_10m_max,wind_direction_10m_dominant";
+
+/// [Open-Meteo](https://open-meteo.com), which doesn't need an API key.
+#[derive(Debug, PartialEq)]
+pub struct OpenMeteo {
+    pub coordinates: (f64, f64),
+    pub unit: GenericWeatherUnit,
+}
+
+impl OpenMeteo {
+    const ARCHIVE_URL: &'static str = "https://archive-api.open-meteo.com/v1/archive";
+
+    fn with_location(&self, mut url: Url) -> Url {
+        let (temperature, wind_speed, precipitation) = match self.unit {
+            GenericWeatherUnit::Metric => ("celsius", "ms", "mm"),
+            GenericWeatherUnit::Imperial => ("fahrenheit", "mph", "inch"),
+        };
+
+        url.query_pairs_mut()
+            .extend_pairs(&[
+                ("latitude", self.coordinates.0.to_string()),
+                ("longitude", self.coordinates.1.to_string()),
+            ])
+            .extend_pairs(&[
+                ("temperature_unit", temperature),
+                ("wind_speed_unit", wind_speed),
+                ("precipitation_unit", precipitation),
+                ("timezone", "auto"),
+                ("timeformat", "unixtime"),
+            ])
+            .finish();
+
+        url
+    }
+}
+
+impl<'c> WeatherApi<'c> for OpenMeteo {
+    const BASE_URL: &'static str = "https://api.open-meteo.com/v1/forecast";
+    const PROVIDER: Provider = Provider::OpenMeteo;
+    type Current = Forecast;
+    type ApiError = OpenMeteoError;
+
+    fn new(config: &'c Config, m: &'c ArgMatches) -> Self {
+        let openmeteo_matches = m.subcommand_matches("openmeteo");
+
+        let coordinates = values_t!(m.values_of("coordinates"), f64)
+            .map(|coordinates| (coordinates[0], coordinates[1]))
+            .ok()
+            .or(config.coordinates)
+            .expect("tried creating open-meteo api without coordinates in config");
+
+        let unit = openmeteo_matches
+            .and_then(|om| value_t!(om.value_of("units"), GenericWeatherUnit).ok())
+            .or_else(|| value_t!(m.value_of("units"), GenericWeatherUnit).ok())
+            .or_else(|| config.openmeteo.as_ref().and_then(|om| om.unit))
+            .or(config.unit)
+            .unwrap_or(GenericWeatherUnit::Metric);
+
+        Self { coordinates, unit }
+    }
+
+    fn url(&self) -> Url {
+        self.with_location(Url::parse(Self::BASE_URL).unwrap())
+    }
+
+    fn current_url(&self) -> Url {
+        let mut url = self.url();
+        url.query_pairs_mut()
+            .append_pair("current", CURRENT)
+            .append_pair("daily", "sunrise,sunset")
+            .append_pair("forecast_days", "1")
+            .finish();
+
+        url
+    }
+
+    fn report(&self, current: Forecast) -> Report {
+        current.into_report(self.unit)
+    }
+}
+
+impl<'f> ForecastApi<'f> for OpenMeteo {
+    type Forecast = Forecast;
+
+    fn forecast_url(&self) -> Url {
+        let mut url = self.url();
+        url.query_pairs_mut()
+            .append_pair("hourly", HOURLY)
+            .append_pair("daily", DAILY)
+            .finish();
+
+        url
+    }
+
+    fn forecast_report(&self, forecast: Forecast) -> Report {
+        forecast.into_report(self.unit)
+    }
+}
+
+impl<'h> HistoricalApi<'h> for OpenMeteo {
+    type Historical = Forecast;
+
+    /// The archive goes by local dates, so ask for the days either side of `time` too.
+    fn historical_url(&self, time: i64) -> Url {
+        let date = Utc
+            .timestamp_opt(time, 0)
+            .single()
+            .unwrap_or_else(Utc::now)
+            .date_naive();
+
+        let mut url = self.with_location(Url::parse(Self::ARCHIVE_URL).unwrap());
+        url.query_pairs_mut()
+            .append_pair("start_date", &(date - Duration::days(1)).to_string())
+            .append_pair("end_date", &(date + Duration::days(1)).to_string())
+            .append_pair("hourly", ARCHIVE_HOURLY)
+            .append_pair("daily", ARCHIVE_DAILY)
+            .finish();
+
+        url
+    }
+
+    fn historical_report(&self, historical: Forecast, time: i64) -> Report {
+        historical.into_historical_report(self.unit, time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OpenMeteoConfig;
+    use crate::weather::{
+        Condition, Distance, DistanceUnit, SpeedUnit, Temperature, TemperatureUnit,
+    };
+
+    #[test]
+    fn it_creates_new_openmeteo_with_units() {
+        let config = Config {
+            coordinates: Some((12.345, -54.321)),
+            unit: Some(GenericWeatherUnit::Metric),
+            openmeteo: Some(OpenMeteoConfig {
+                unit: Some(GenericWeatherUnit::Imperial),
+            }),
+            ..Default::default()
+        };
+
+        let matches = clap::App::new("name").get_matches_from(vec!["name"]);
+        let api = OpenMeteo::new(&config, &matches);
+
+        assert_eq!(
+            OpenMeteo {
+                coordinates: (12.345, -54.321),
+                unit: GenericWeatherUnit::Imperial,
+            },
+            api
+        );
+        assert_eq!(
+            Url::parse(
+                "https://api.open-meteo.com/v1/forecast?latitude=12.345&longitude=-54.321\
+                 &temperature_unit=fahrenheit&wind_speed_unit=mph&precipitation_unit=inch\
+                 &timezone=auto&timeformat=unixtime"
+            )
+            .unwrap(),
+            api.url()
+        );
+    }
+
+    #[test]
+    fn it_asks_the_archive_for_the_days_around_a_time() {
+        let api = OpenMeteo {
+            coordinates: (12.345, -54.321),
+            unit: GenericWeatherUnit::Metric,
+        };
+        let url = api.historical_url(1_542_143_061);
+
+        assert_eq!(Some("archive-api.open-meteo.com"), url.host_str());
+        assert!(url
+            .query_pairs()
+            .any(|(key, value)| key == "start_date" && value == "2018-11-12"));
+        assert!(url
+            .query_pairs()
+            .any(|(key, value)| key == "end_date" && value == "2018-11-14"));
+    }
+
+    #[test]
+    fn it_normalizes_current_conditions() {
+        let current: Forecast = serde_json::from_str(
+            r#"{
+                "latitude": 49.9,
+                "longitude": -97.14,
+                "generationtime_ms": 0.05,
+                "utc_offset_seconds": -21600,
+                "timezone": "America/Winnipeg",
+                "timezone_abbreviation": "CST",
+                "elevation": 239.0,
+                "current_units": { "time": "unixtime", "temperature_2m": "°C" },
+                "current": {
+                    "time": 1542143061, "interval": 900, "temperature_2m": -3.5,
+                    "relative_humidity_2m": 74, "apparent_temperature": -8.2, "is_day": 0,
+                    "precipitation": 0.0, "weather_code": 71, "cloud_cover": 100,
+                    "pressure_msl": 1021.3, "wind_speed_10m": 14.8, "wind_direction_10m": 310,
+                    "wind_gusts_10m": 25.9, "visibility": 8000.0
+                },
+                "daily": { "time": [1542088800], "sunrise": [1542116053], "sunset": [1542150213] }
+            }"#,
+        )
+        .unwrap();
+
+        let api = OpenMeteo {
+            coordinates: (49.9, -97.14),
+            unit: GenericWeatherUnit::Metric,
+        };
+        let report = api.report(current);
+        let current = report.current.unwrap();
+
+        assert_eq!(TemperatureUnit::Celsius, report.units.temperature);
+        assert_eq!(SpeedUnit::MetresPerSecond, report.units.speed);
+        assert_eq!(Condition::Snow, current.condition);
+        assert_eq!(Some(String::from("Slight snow fall")), current.summary);
+        assert_eq!(Some(0.74), current.humidity);
+        assert_eq!(
+            Some(Distance::new(8., DistanceUnit::Kilometres)),
+            current.visibility
+        );
+        assert_eq!(Some(1_542_150_213), current.sunset);
+    }
+
+    #[test]
+    fn it_picks_the_hour_asked_for_from_the_archive() {
+        let historical: Forecast = serde_json::from_str(
+            r#"{
+                "latitude": 49.9,
+                "longitude": -97.14,
+                "timezone": "America/Winnipeg",
+                "hourly": {
+                    "time": [1542139200, 1542142800, 1542146400],
+                    "temperature_2m": [-3.0, -3.5, null],
+                    "weather_code": [0, 3, 3],
+                    "is_day": [1, 0, 0]
+                },
+                "daily": {
+                    "time": [1542088800],
+                    "temperature_2m_max": [-1.0],
+                    "temperature_2m_min": [-9.5],
+                    "weather_code": [3]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let api = OpenMeteo {
+            coordinates: (49.9, -97.14),
+            unit: GenericWeatherUnit::Metric,
+        };
+        let report = api.historical_report(historical, 1_542_143_061);
+        let current = report.current.unwrap();
+
+        assert_eq!(1_542_142_800, current.time);
+        assert_eq!(
+            Some(Temperature::new(-3.5, TemperatureUnit::Celsius)),
+            current.temperature
+        );
+        assert_eq!(Condition::Cloudy, current.condition);
+        assert_eq!(3, report.hourly.len());
+        assert_eq!(None, report.hourly[2].temperature);
+        assert_eq!(
+            Some(Temperature::new(-9.5, TemperatureUnit::Celsius)),
+            report.daily[0].temperature_low
+        );
+    }
+}
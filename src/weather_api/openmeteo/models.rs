@@ -0,0 +1,335 @@
+use crate::config::GenericWeatherUnit;
+use crate::weather::{
+    Condition, DailySummary, DistanceUnit, HourlyPoint, Location, Observation, Report, Unit, Units,
+};
+use crate::weather_api::Provider;
+use failure::Fail;
+use serde_derive::Deserialize;
+use std::fmt;
+
+#[derive(Fail, Debug, Deserialize)]
+pub struct OpenMeteoError {
+    error: bool,
+    reason: String,
+}
+
+impl fmt::Display for OpenMeteoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "open-meteo error: {}", self.reason)
+    }
+}
+
+/// A response from the forecast or archive endpoint. Which blocks are there depends on what was
+/// asked for.
+#[derive(Debug, Deserialize)]
+pub struct Forecast {
+    latitude: f64,
+    longitude: f64,
+    #[allow(dead_code)]
+    generationtime_ms: Option<f64>,
+    #[allow(dead_code)]
+    utc_offset_seconds: Option<i32>,
+    timezone: Option<String>,
+    #[allow(dead_code)]
+    timezone_abbreviation: Option<String>,
+    #[allow(dead_code)]
+    elevation: Option<f64>,
+    current: Option<Current>,
+    hourly: Option<Hourly>,
+    daily: Option<Daily>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Current {
+    time: i64,
+    /// Seconds the values are averaged over.
+    #[allow(dead_code)]
+    interval: Option<i64>,
+    temperature_2m: Option<f64>,
+    relative_humidity_2m: Option<f64>,
+    apparent_temperature: Option<f64>,
+    dew_point_2m: Option<f64>,
+    is_day: Option<u8>,
+    precipitation: Option<f64>,
+    weather_code: Option<u8>,
+    cloud_cover: Option<f64>,
+    pressure_msl: Option<f64>,
+    wind_speed_10m: Option<f64>,
+    wind_direction_10m: Option<f64>,
+    wind_gusts_10m: Option<f64>,
+    /// In metres.
+    visibility: Option<f64>,
+    uv_index: Option<f64>,
+}
+
+/// Hourly values, as a column per variable.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Hourly {
+    time: Vec<i64>,
+    temperature_2m: Vec<Option<f64>>,
+    relative_humidity_2m: Vec<Option<f64>>,
+    apparent_temperature: Vec<Option<f64>>,
+    dew_point_2m: Vec<Option<f64>>,
+    precipitation_probability: Vec<Option<f64>>,
+    /// Over the preceding hour.
+    precipitation: Vec<Option<f64>>,
+    weather_code: Vec<Option<u8>>,
+    pressure_msl: Vec<Option<f64>>,
+    cloud_cover: Vec<Option<f64>>,
+    wind_speed_10m: Vec<Option<f64>>,
+    wind_direction_10m: Vec<Option<f64>>,
+    wind_gusts_10m: Vec<Option<f64>>,
+    is_day: Vec<Option<u8>>,
+}
+
+/// Daily values, as a column per variable.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Daily {
+    time: Vec<i64>,
+    weather_code: Vec<Option<u8>>,
+    temperature_2m_max: Vec<Option<f64>>,
+    temperature_2m_min: Vec<Option<f64>>,
+    apparent_temperature_max: Vec<Option<f64>>,
+    apparent_temperature_min: Vec<Option<f64>>,
+    sunrise: Vec<Option<i64>>,
+    sunset: Vec<Option<i64>>,
+    uv_index_max: Vec<Option<f64>>,
+    precipitation_probability_max: Vec<Option<f64>>,
+    wind_speed_10m_max: Vec<Option<f64>>,
+    wind_direction_10m_dominant: Vec<Option<f64>>,
+}
+
+impl Forecast {
+    pub fn into_report(self, unit: GenericWeatherUnit) -> Report {
+        let units = Units::from(unit);
+        let mut report = self.base_report(units);
+
+        report.daily = self
+            .daily
+            .as_ref()
+            .map(|daily| daily.summaries(units))
+            .unwrap_or_default();
+        report.current = self.current.map(|current| {
+            let mut observation = current.into_observation(units);
+            if let Some(today) = report.daily.first() {
+                observation.sunrise = today.sunrise;
+                observation.sunset = today.sunset;
+            }
+            observation
+        });
+        report.hourly = self
+            .hourly
+            .as_ref()
+            .map(|hourly| hourly.points(units))
+            .unwrap_or_default();
+
+        report
+    }
+
+    /// Like [`into_report`](Self::into_report), with the hour `time` is in as the current
+    /// conditions.
+    pub fn into_historical_report(self, unit: GenericWeatherUnit, time: i64) -> Report {
+        let units = Units::from(unit);
+        let mut report = self.base_report(units);
+
+        report.daily = self
+            .daily
+            .as_ref()
+            .map(|daily| daily.summaries(units))
+            .unwrap_or_default();
+        report.current = self.hourly.as_ref().and_then(|hourly| {
+            let hour = hourly.time.iter().rposition(|start| *start <= time)?;
+            let mut observation = hourly.observation(hour, units);
+            if let Some(day) = report.daily.iter().rev().find(|day| day.time <= time) {
+                observation.sunrise = day.sunrise;
+                observation.sunset = day.sunset;
+            }
+            Some(observation)
+        });
+        report.hourly = self
+            .hourly
+            .as_ref()
+            .map(|hourly| hourly.points(units))
+            .unwrap_or_default();
+
+        report
+    }
+
+    fn base_report(&self, units: Units) -> Report {
+        let mut report = Report::new(Provider::OpenMeteo, units);
+        report.location = Location {
+            latitude: Some(self.latitude),
+            longitude: Some(self.longitude),
+            timezone: self.timezone.clone(),
+            ..Default::default()
+        };
+
+        report
+    }
+}
+
+impl Current {
+    fn into_observation(self, units: Units) -> Observation {
+        let day = self.is_day.map(|is_day| is_day != 0);
+
+        Observation {
+            summary: self.weather_code.and_then(description).map(String::from),
+            condition: condition(self.weather_code, day),
+            temperature: units.temperature.of(self.temperature_2m),
+            apparent_temperature: units.temperature.of(self.apparent_temperature),
+            dew_point: units.temperature.of(self.dew_point_2m),
+            humidity: self.relative_humidity_2m.map(percent),
+            pressure: units.pressure.of(self.pressure_msl),
+            wind_speed: units.speed.of(self.wind_speed_10m),
+            wind_gust: units.speed.of(self.wind_gusts_10m),
+            wind_bearing: self.wind_direction_10m,
+            cloud_cover: self.cloud_cover.map(percent),
+            // Visibility comes in metres whatever the units.
+            visibility: DistanceUnit::Kilometres.of(self.visibility.map(|metres| metres / 1000.)),
+            uv_index: self.uv_index,
+            precip_intensity: units.precipitation.of(self.precipitation),
+            ..Observation::new(self.time)
+        }
+    }
+}
+
+impl Hourly {
+    fn points(&self, units: Units) -> Vec<HourlyPoint> {
+        (0..self.time.len()).map(|i| self.point(i, units)).collect()
+    }
+
+    fn point(&self, i: usize, units: Units) -> HourlyPoint {
+        let code = at(&self.weather_code, i);
+
+        HourlyPoint {
+            summary: code.and_then(description).map(String::from),
+            condition: condition(code, at(&self.is_day, i).map(|is_day| is_day != 0)),
+            temperature: units.temperature.of(at(&self.temperature_2m, i)),
+            apparent_temperature: units.temperature.of(at(&self.apparent_temperature, i)),
+            humidity: at(&self.relative_humidity_2m, i).map(percent),
+            pressure: units.pressure.of(at(&self.pressure_msl, i)),
+            wind_speed: units.speed.of(at(&self.wind_speed_10m, i)),
+            wind_bearing: at(&self.wind_direction_10m, i),
+            cloud_cover: at(&self.cloud_cover, i).map(percent),
+            precip_intensity: units.precipitation.of(at(&self.precipitation, i)),
+            precip_probability: at(&self.precipitation_probability, i).map(percent),
+            ..HourlyPoint::new(self.time[i])
+        }
+    }
+
+    fn observation(&self, i: usize, units: Units) -> Observation {
+        let point = self.point(i, units);
+
+        Observation {
+            summary: point.summary,
+            condition: point.condition,
+            temperature: point.temperature,
+            apparent_temperature: point.apparent_temperature,
+            dew_point: units.temperature.of(at(&self.dew_point_2m, i)),
+            humidity: point.humidity,
+            pressure: point.pressure,
+            wind_speed: point.wind_speed,
+            wind_gust: units.speed.of(at(&self.wind_gusts_10m, i)),
+            wind_bearing: point.wind_bearing,
+            cloud_cover: point.cloud_cover,
+            precip_intensity: point.precip_intensity,
+            precip_probability: point.precip_probability,
+            ..Observation::new(point.time)
+        }
+    }
+}
+
+impl Daily {
+    fn summaries(&self, units: Units) -> Vec<DailySummary> {
+        (0..self.time.len())
+            .map(|i| {
+                let code = at(&self.weather_code, i);
+
+                DailySummary {
+                    summary: code.and_then(description).map(String::from),
+                    condition: condition(code, Some(true)),
+                    temperature_high: units.temperature.of(at(&self.temperature_2m_max, i)),
+                    temperature_low: units.temperature.of(at(&self.temperature_2m_min, i)),
+                    apparent_temperature_high: units
+                        .temperature
+                        .of(at(&self.apparent_temperature_max, i)),
+                    apparent_temperature_low: units
+                        .temperature
+                        .of(at(&self.apparent_temperature_min, i)),
+                    wind_speed: units.speed.of(at(&self.wind_speed_10m_max, i)),
+                    wind_bearing: at(&self.wind_direction_10m_dominant, i),
+                    precip_probability: at(&self.precipitation_probability_max, i).map(percent),
+                    uv_index: at(&self.uv_index_max, i),
+                    sunrise: at(&self.sunrise, i),
+                    sunset: at(&self.sunset, i),
+                    ..DailySummary::new(self.time[i])
+                }
+            })
+            .collect()
+    }
+}
+
+fn at<T: Copy>(column: &[Option<T>], i: usize) -> Option<T> {
+    column.get(i).cloned().flatten()
+}
+
+fn percent(value: f64) -> f64 {
+    value / 100.
+}
+
+/// Map a WMO weather interpretation code onto a [`Condition`].
+///
+/// See the bottom of <https://open-meteo.com/en/docs>.
+fn condition(code: Option<u8>, day: Option<bool>) -> Condition {
+    let night = day == Some(false);
+
+    match code {
+        Some(0) if night => Condition::ClearNight,
+        Some(0) => Condition::ClearDay,
+        Some(1) | Some(2) if night => Condition::PartlyCloudyNight,
+        Some(1) | Some(2) => Condition::PartlyCloudyDay,
+        Some(3) => Condition::Cloudy,
+        Some(45) | Some(48) => Condition::Fog,
+        Some(56) | Some(57) | Some(66) | Some(67) => Condition::Sleet,
+        Some(51..=67) | Some(80..=82) => Condition::Rain,
+        Some(71..=77) | Some(85) | Some(86) => Condition::Snow,
+        Some(95..=99) => Condition::Thunderstorm,
+        _ => Condition::Unknown,
+    }
+}
+
+fn description(code: u8) -> Option<&'static str> {
+    Some(match code {
+        0 => "Clear sky",
+        1 => "Mainly clear",
+        2 => "Partly cloudy",
+        3 => "Overcast",
+        45 => "Fog",
+        48 => "Depositing rime fog",
+        51 => "Light drizzle",
+        53 => "Moderate drizzle",
+        55 => "Dense drizzle",
+        56 => "Light freezing drizzle",
+        57 => "Dense freezing drizzle",
+        61 => "Slight rain",
+        63 => "Moderate rain",
+        65 => "Heavy rain",
+        66 => "Light freezing rain",
+        67 => "Heavy freezing rain",
+        71 => "Slight snow fall",
+        73 => "Moderate snow fall",
+        75 => "Heavy snow fall",
+        77 => "Snow grains",
+        80 => "Slight rain showers",
+        81 => "Moderate rain showers",
+        82 => "Violent rain showers",
+        85 => "Slight snow showers",
+        86 => "Heavy snow showers",
+        95 => "Thunderstorm",
+        96 => "Thunderstorm with slight hail",
+        99 => "Thunderstorm with heavy hail",
+        _ => return None,
+    })
+}
@@ -1,18 +1,35 @@
 use super::Config;
-use super::{ForecastApi, WeatherApi};
+use super::{rebase, ForecastApi, HistoricalApi, Provider, WeatherApi};
 use crate::config::{GenericWeatherUnit, OwmUnit};
+use crate::weather::Report;
 use clap::ArgMatches;
 use url::Url;
 
 mod models;
+mod one_call;
 
 pub use self::models::{Current, Forecast, OwmError};
+pub use self::one_call::{OneCall as OneCallResponse, TimeMachine};
 
 #[derive(Debug, PartialEq)]
 pub struct Owm<'a> {
     pub key: &'a str,
     pub location: Location<'a>,
     pub unit: Option<OwmUnit>,
+    /// Replaces `https://api.openweathermap.org`.
+    pub base_url: Option<&'a str>,
+}
+
+/// OpenWeatherMap's One Call API, which gives everything for a location in one request.
+///
+/// It shares the `[owm]` config, but only works with coordinates.
+#[derive(Debug, PartialEq)]
+pub struct OneCall<'a> {
+    pub key: &'a str,
+    pub coordinates: (f64, f64),
+    pub unit: Option<OwmUnit>,
+    /// Replaces `https://api.openweathermap.org`.
+    pub base_url: Option<&'a str>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -23,6 +40,7 @@ pub enum Location<'a> {
 
 impl<'a, 'c: 'a> WeatherApi<'c> for Owm<'a> {
     const BASE_URL: &'static str = "https://api.openweathermap.org/data/2.5";
+    const PROVIDER: Provider = Provider::Owm;
     type Current = Current;
     type ApiError = OwmError;
 
@@ -52,19 +70,25 @@ impl<'a, 'c: 'a> WeatherApi<'c> for Owm<'a> {
             .or(owm.unit)
             .or_else(|| config.unit.map(OwmUnit::from));
 
+        let base_url = m
+            .subcommand_matches("owm")
+            .and_then(|om| om.value_of("base-url"))
+            .or(owm.base_url.as_deref());
+
         Self {
             key,
             location,
             unit,
+            base_url,
         }
     }
 
     fn url(&self) -> Url {
-        let mut url = Url::parse_with_params(
-            &format!("{}/weather", Self::BASE_URL),
-            &[("appid", self.key)],
-        )
-        .unwrap();
+        let mut url = rebase(Self::BASE_URL, self.base_url);
+        url.path_segments_mut().unwrap().push("weather");
+        url.query_pairs_mut()
+            .append_pair("appid", self.key)
+            .finish();
 
         match self.location {
             Location::Id(id) => url.query_pairs_mut().append_pair("id", id).finish(),
@@ -88,6 +112,14 @@ impl<'a, 'c: 'a> WeatherApi<'c> for Owm<'a> {
     fn current_url(&self) -> Url {
         self.url()
     }
+
+    fn api_key(&self) -> Option<&str> {
+        Some(self.key)
+    }
+
+    fn report(&self, current: Current) -> Report {
+        current.into_report(self.unit)
+    }
 }
 
 impl<'a, 'f: 'a> ForecastApi<'f> for Owm<'a> {
@@ -99,12 +131,114 @@ impl<'a, 'f: 'a> ForecastApi<'f> for Owm<'a> {
 
         url
     }
+
+    fn forecast_report(&self, forecast: Forecast) -> Report {
+        forecast.into_report(self.unit)
+    }
+}
+
+impl<'a, 'c: 'a> WeatherApi<'c> for OneCall<'a> {
+    const BASE_URL: &'static str = "https://api.openweathermap.org/data/3.0/onecall";
+    const PROVIDER: Provider = Provider::OwmOneCall;
+    type Current = OneCallResponse;
+    type ApiError = OwmError;
+
+    fn new(config: &'c Config, m: &'c ArgMatches) -> Self {
+        let owm = Owm::new(config, m);
+
+        let coordinates = match owm.location {
+            Location::Coord(lat, lon) => (lat, lon),
+            Location::Id(_) => config
+                .coordinates
+                .expect("One Call needs coordinates, not a location id."),
+        };
+
+        Self {
+            key: owm.key,
+            coordinates,
+            unit: owm.unit,
+            base_url: owm.base_url,
+        }
+    }
+
+    fn url(&self) -> Url {
+        let mut url = rebase(Self::BASE_URL, self.base_url);
+        url.query_pairs_mut()
+            .extend_pairs(&[
+                ("appid", self.key.to_string()),
+                ("lat", self.coordinates.0.to_string()),
+                ("lon", self.coordinates.1.to_string()),
+            ])
+            .finish();
+
+        if let Some(unit) = self.unit {
+            url.query_pairs_mut()
+                .append_pair("units", &unit.to_string())
+                .finish();
+        }
+
+        url
+    }
+
+    fn current_url(&self) -> Url {
+        let mut url = self.url();
+        url.query_pairs_mut()
+            .append_pair("exclude", "minutely,hourly,daily,alerts")
+            .finish();
+
+        url
+    }
+
+    fn api_key(&self) -> Option<&str> {
+        Some(self.key)
+    }
+
+    fn report(&self, current: OneCallResponse) -> Report {
+        current.into_report(self.unit)
+    }
+}
+
+impl<'a, 'f: 'a> ForecastApi<'f> for OneCall<'a> {
+    type Forecast = OneCallResponse;
+
+    fn forecast_url(&self) -> Url {
+        self.url()
+    }
+
+    fn forecast_report(&self, forecast: OneCallResponse) -> Report {
+        forecast.into_report(self.unit)
+    }
+}
+
+impl<'a, 'h: 'a> HistoricalApi<'h> for OneCall<'a> {
+    type Historical = TimeMachine;
+
+    fn historical_url(&self, time: i64) -> Url {
+        let mut url = self.url();
+        url.path_segments_mut().unwrap().push("timemachine");
+        url.query_pairs_mut()
+            .append_pair("dt", &time.to_string())
+            .finish();
+
+        url
+    }
+
+    fn historical_report(&self, historical: TimeMachine, _time: i64) -> Report {
+        historical.into_report(self.unit)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::{Config, OwmConfig};
+    use crate::output::{ForecastTable, Template};
+    use crate::weather::{
+        Condition, Distance, DistanceUnit, PrecipitationRate, PrecipitationUnit, Temperature,
+        TemperatureUnit,
+    };
+    use chrono::FixedOffset;
+    use clap::{App, Arg, SubCommand};
 
     impl<'a> Default for Owm<'a> {
         fn default() -> Self {
@@ -112,6 +246,7 @@ mod tests {
                 key: "",
                 location: Location::Id(""),
                 unit: None,
+                base_url: None,
             }
         }
     }
@@ -140,6 +275,7 @@ mod tests {
                 key: "owm_key",
                 location: Location::Id("a1b2c3d4"),
                 unit: None,
+                base_url: None,
             },
             api
         );
@@ -153,6 +289,7 @@ mod tests {
                 key: String::from("owm_key"),
                 location_id: Some(String::from("a1b2c3d4")),
                 unit: Some(OwmUnit::Imperial),
+                ..Default::default()
             }),
             ..Default::default()
         };
@@ -170,6 +307,7 @@ mod tests {
                 key: "owm_key",
                 location: Location::Id("a1b2c3d4"),
                 unit: Some(OwmUnit::Imperial),
+                base_url: None,
             },
             api
         );
@@ -200,6 +338,7 @@ mod tests {
                 key: "owm_key",
                 location: Location::Coord(12.345, -54.321),
                 unit: None,
+                base_url: None,
             },
             api
         );
@@ -231,6 +370,7 @@ mod tests {
                 key: "owm_key",
                 location: Location::Coord(12.345, -54.321),
                 unit: Some(OwmUnit::Metric),
+                base_url: None,
             },
             api
         );
@@ -251,4 +391,422 @@ mod tests {
         let matches = clap::App::new("name").get_matches_from(vec!["name"]);
         Owm::new(&config, &matches);
     }
+
+    #[test]
+    fn it_normalizes_current_conditions() {
+        let current: Current = serde_json::from_str(
+            r#"{
+                "coord": { "lon": -97.14, "lat": 49.9 },
+                "weather": [
+                    { "id": 800, "main": "Clear", "description": "clear sky", "icon": "01n" }
+                ],
+                "base": "stations",
+                "main": { "temp": -3.5, "pressure": 1021, "humidity": 74 },
+                "visibility": 24140,
+                "wind": { "speed": 4.1, "deg": 310 },
+                "clouds": { "all": 1 },
+                "dt": 1542143061,
+                "sys": { "country": "CA", "sunrise": 1542116053, "sunset": 1542150213 },
+                "id": 6183235,
+                "name": "Winnipeg",
+                "cod": 200
+            }"#,
+        )
+        .unwrap();
+
+        let api = Owm {
+            unit: Some(OwmUnit::Metric),
+            ..Default::default()
+        };
+        let report = api.report(current);
+        let current = report.current.unwrap();
+
+        assert_eq!(Some(String::from("Winnipeg")), report.location.name);
+        assert_eq!(TemperatureUnit::Celsius, report.units.temperature);
+        assert_eq!(Condition::ClearNight, current.condition);
+        assert_eq!(
+            Some(Temperature::new(-3.5, TemperatureUnit::Celsius)),
+            current.temperature
+        );
+        assert_eq!(Some(0.74), current.humidity);
+        assert_eq!(
+            Some(Distance::new(24.14, DistanceUnit::Kilometres)),
+            current.visibility
+        );
+        assert_eq!(Some(1_542_150_213), current.sunset);
+    }
+
+    #[test]
+    fn it_shows_the_sun_times_at_the_location() {
+        let current: Current = serde_json::from_str(
+            r#"{
+                "coord": { "lon": -97.14, "lat": 49.9 },
+                "weather": [
+                    { "id": 800, "main": "Clear", "description": "clear sky", "icon": "01n" }
+                ],
+                "base": "stations",
+                "main": { "temp": -3.5, "pressure": 1021, "humidity": 74 },
+                "visibility": 24140,
+                "wind": { "speed": 4.1, "deg": 310 },
+                "clouds": { "all": 1 },
+                "dt": 1542143061,
+                "sys": { "country": "CA", "sunrise": 1542116053, "sunset": 1542150213 },
+                "timezone": -21600,
+                "id": 6183235,
+                "name": "Winnipeg",
+                "cod": 200
+            }"#,
+        )
+        .unwrap();
+
+        let api = Owm {
+            unit: Some(OwmUnit::Metric),
+            ..Default::default()
+        };
+        let report = api.report(current);
+        let template = "{sunrise} {sunset}".parse::<Template>().unwrap();
+
+        assert_eq!(FixedOffset::west_opt(6 * 3600), report.location.utc_offset);
+        // 13:34 and 23:03 UTC, wherever this runs.
+        assert_eq!("07:34 17:03", template.render(&report));
+    }
+
+    #[test]
+    fn it_normalizes_a_forecast() {
+        let forecast: Forecast = serde_json::from_str(
+            r#"{
+                "cod": "200",
+                "message": 0,
+                "cnt": 1,
+                "list": [
+                    {
+                        "dt": 1542121200,
+                        "main": {
+                            "temp": -3.46, "feels_like": -8.1, "temp_min": -3.46,
+                            "temp_max": -3.2, "pressure": 1021, "sea_level": 1021,
+                            "grnd_level": 992, "humidity": 80, "temp_kf": -0.26
+                        },
+                        "weather": [
+                            { "id": 600, "main": "Snow", "description": "light snow", "icon": "13d" }
+                        ],
+                        "clouds": { "all": 90 },
+                        "wind": { "speed": 4.1, "deg": 310, "gust": 7.2 },
+                        "visibility": 10000,
+                        "pop": 0.4,
+                        "snow": { "3h": 0.75 },
+                        "sys": { "pod": "d" },
+                        "dt_txt": "2018-11-13 15:00:00"
+                    }
+                ],
+                "city": {
+                    "id": 6183235,
+                    "name": "Winnipeg",
+                    "coord": { "lat": 49.9, "lon": -97.14 },
+                    "country": "CA",
+                    "population": 1000,
+                    "timezone": -21600,
+                    "sunrise": 1542116053,
+                    "sunset": 1542150213
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let api = Owm {
+            unit: Some(OwmUnit::Metric),
+            ..Default::default()
+        };
+        let report = api.forecast_report(forecast);
+        let point = &report.hourly[0];
+
+        assert_eq!(Some(String::from("Winnipeg")), report.location.name);
+        assert_eq!(1, report.hourly.len());
+        assert_eq!(Condition::Snow, point.condition);
+        assert_eq!(
+            Some(Temperature::new(-8.1, TemperatureUnit::Celsius)),
+            point.apparent_temperature
+        );
+        assert_eq!(Some(0.4), point.precip_probability);
+        assert_eq!(
+            Some(PrecipitationRate::new(
+                0.25,
+                PrecipitationUnit::MillimetresPerHour
+            )),
+            point.precip_intensity
+        );
+    }
+
+    #[test]
+    fn it_groups_the_forecast_into_the_locations_days() {
+        let forecast: Forecast = serde_json::from_str(
+            r#"{
+                "cod": "200",
+                "message": 0,
+                "cnt": 2,
+                "list": [
+                    {
+                        "dt": 1542121200,
+                        "main": { "temp": -3.46, "pressure": 1021, "humidity": 80 },
+                        "weather": [
+                            { "id": 804, "main": "Clouds", "description": "overcast clouds", "icon": "04d" }
+                        ],
+                        "dt_txt": "2018-11-13 15:00:00"
+                    },
+                    {
+                        "dt": 1542164400,
+                        "main": { "temp": -10, "pressure": 1024, "humidity": 85 },
+                        "weather": [
+                            { "id": 600, "main": "Snow", "description": "light snow", "icon": "13n" }
+                        ],
+                        "dt_txt": "2018-11-14 03:00:00"
+                    }
+                ],
+                "city": {
+                    "id": 6183235,
+                    "name": "Winnipeg",
+                    "coord": { "lat": 49.9, "lon": -97.14 },
+                    "country": "CA",
+                    "timezone": -21600,
+                    "sunrise": 1542116053,
+                    "sunset": 1542150213
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let api = Owm {
+            unit: Some(OwmUnit::Metric),
+            ..Default::default()
+        };
+        let report = api.forecast_report(forecast);
+
+        assert_eq!(FixedOffset::west_opt(6 * 3600), report.location.utc_offset);
+        // 03:00 UTC on the 14th is still the evening of the 13th in Winnipeg, wherever this runs.
+        assert_eq!(
+            "Winnipeg, CA (OpenWeatherMap)\n\
+             \n\
+             Tue Nov 13\n  \
+             09:00  ☁     -3.5°C                       overcast clouds\n  \
+             21:00  ❄    -10.0°C                       light snow\n",
+            ForecastTable(&report).to_string()
+        );
+    }
+
+    #[test]
+    fn it_builds_one_call_urls() {
+        let config = Config {
+            coordinates: Some((12.345, -54.321)),
+            owm: Some(OwmConfig {
+                key: String::from("owm_key"),
+                location_id: Some(String::from("a1b2c3d4")),
+                unit: Some(OwmUnit::Metric),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let matches = clap::App::new("name").get_matches_from(vec!["name"]);
+        let api = OneCall::new(&config, &matches);
+
+        assert_eq!((12.345, -54.321), api.coordinates);
+        assert_eq!(
+            Url::parse(
+                "https://api.openweathermap.org/data/3.0/onecall?\
+                 appid=owm_key&lat=12.345&lon=-54.321&units=metric\
+                 &exclude=minutely%2Chourly%2Cdaily%2Calerts"
+            )
+            .unwrap(),
+            api.current_url()
+        );
+        assert_eq!(
+            Url::parse(
+                "https://api.openweathermap.org/data/3.0/onecall/timemachine?\
+                 appid=owm_key&lat=12.345&lon=-54.321&units=metric&dt=1542143061"
+            )
+            .unwrap(),
+            api.historical_url(1_542_143_061)
+        );
+    }
+
+    #[test]
+    fn it_sends_requests_to_the_base_url() {
+        let config = Config {
+            coordinates: Some((12.345, -54.321)),
+            owm: Some(OwmConfig {
+                key: String::from("owm_key"),
+                base_url: Some(String::from("http://localhost:8080")),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let matches = App::new("name")
+            .subcommand(
+                SubCommand::with_name("owm").arg(
+                    Arg::with_name("base-url")
+                        .long("base-url")
+                        .takes_value(true),
+                ),
+            )
+            .get_matches_from(vec![
+                "name",
+                "owm",
+                "--base-url",
+                "http://127.0.0.1:9000/owm",
+            ]);
+        let api = Owm::new(&config, &matches);
+
+        assert_eq!(
+            Url::parse(
+                "http://127.0.0.1:9000/owm/data/2.5/forecast?\
+                 appid=owm_key&lat=12.345&lon=-54.321"
+            )
+            .unwrap(),
+            api.forecast_url()
+        );
+
+        let matches = App::new("name").get_matches_from(vec!["name"]);
+        let api = OneCall::new(&config, &matches);
+
+        assert_eq!(
+            Url::parse(
+                "http://localhost:8080/data/3.0/onecall/timemachine?\
+                 appid=owm_key&lat=12.345&lon=-54.321&dt=1542143061"
+            )
+            .unwrap(),
+            api.historical_url(1_542_143_061)
+        );
+    }
+
+    #[test]
+    fn it_normalizes_one_call() {
+        let one_call: OneCallResponse = serde_json::from_str(
+            r#"{
+                "lat": 49.9,
+                "lon": -97.14,
+                "timezone": "America/Winnipeg",
+                "timezone_offset": -21600,
+                "current": {
+                    "dt": 1542143061, "sunrise": 1542116053, "sunset": 1542150213,
+                    "temp": -3.5, "feels_like": -8.2, "pressure": 1021, "humidity": 74,
+                    "dew_point": -7.1, "uvi": 0.4, "clouds": 1, "visibility": 10000,
+                    "wind_speed": 4.1, "wind_deg": 310,
+                    "weather": [
+                        { "id": 800, "main": "Clear", "description": "clear sky", "icon": "01d" }
+                    ]
+                },
+                "minutely": [{ "dt": 1542143100, "precipitation": 0 }],
+                "hourly": [
+                    {
+                        "dt": 1542142800, "temp": -3.6, "feels_like": -8.3, "pressure": 1021,
+                        "humidity": 75, "clouds": 90, "wind_speed": 4.0, "wind_deg": 300,
+                        "pop": 0.3, "snow": { "1h": 0.2 },
+                        "weather": [
+                            { "id": 600, "main": "Snow", "description": "light snow", "icon": "13n" }
+                        ]
+                    }
+                ],
+                "daily": [
+                    {
+                        "dt": 1542132000, "sunrise": 1542116053, "sunset": 1542150213,
+                        "summary": "Light snow in the evening",
+                        "temp": { "day": -3, "min": -9.5, "max": -2.1, "night": -8, "eve": -5, "morn": -7 },
+                        "feels_like": { "day": -7, "night": -14, "eve": -10, "morn": -12 },
+                        "pressure": 1021, "humidity": 80, "wind_speed": 4.5, "wind_deg": 305,
+                        "clouds": 60, "pop": 0.5, "snow": 1.2, "uvi": 0.8,
+                        "weather": [
+                            { "id": 600, "main": "Snow", "description": "light snow", "icon": "13d" }
+                        ]
+                    }
+                ],
+                "alerts": [
+                    {
+                        "sender_name": "Environment Canada",
+                        "event": "Snowfall warning",
+                        "start": 1542140000,
+                        "end": 1542200000,
+                        "description": "Heavy snow is expected.",
+                        "tags": ["Snow/Ice"]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let api = OneCall {
+            key: "",
+            coordinates: (49.9, -97.14),
+            unit: Some(OwmUnit::Metric),
+            base_url: None,
+        };
+        let report = api.forecast_report(one_call);
+        let current = report.current.as_ref().unwrap();
+        let day = &report.daily[0];
+
+        assert_eq!(Provider::OwmOneCall, report.provider);
+        assert_eq!(
+            Some(String::from("America/Winnipeg")),
+            report.location.timezone
+        );
+        assert_eq!(Condition::ClearDay, current.condition);
+        assert_eq!(Some(0.74), current.humidity);
+        assert_eq!(
+            Some(Distance::new(10., DistanceUnit::Kilometres)),
+            current.visibility
+        );
+        assert_eq!(Condition::Snow, report.hourly[0].condition);
+        assert_eq!(
+            Some(PrecipitationRate::new(
+                0.2,
+                PrecipitationUnit::MillimetresPerHour
+            )),
+            report.hourly[0].precip_intensity
+        );
+        assert_eq!(
+            Some(Temperature::new(-2.1, TemperatureUnit::Celsius)),
+            day.temperature_high
+        );
+        assert_eq!(
+            Some(Temperature::new(-14., TemperatureUnit::Celsius)),
+            day.apparent_temperature_low
+        );
+        assert_eq!(Some(String::from("Light snow in the evening")), day.summary);
+        assert_eq!("Snowfall warning", report.alerts[0].title);
+        assert_eq!(Some(1_542_200_000), report.alerts[0].expires);
+    }
+
+    #[test]
+    fn it_normalizes_one_call_history() {
+        let historical: TimeMachine = serde_json::from_str(
+            r#"{
+                "lat": 49.9,
+                "lon": -97.14,
+                "timezone": "America/Winnipeg",
+                "timezone_offset": -21600,
+                "data": [
+                    {
+                        "dt": 1542143061, "temp": -3.5, "humidity": 74,
+                        "weather": [
+                            { "id": 804, "main": "Clouds", "description": "overcast clouds", "icon": "04n" }
+                        ]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let api = OneCall {
+            key: "",
+            coordinates: (49.9, -97.14),
+            unit: None,
+            base_url: None,
+        };
+        let report = api.historical_report(historical, 1_542_143_061);
+        let current = report.current.unwrap();
+
+        assert_eq!(TemperatureUnit::Kelvin, report.units.temperature);
+        assert_eq!(1_542_143_061, current.time);
+        assert_eq!(Condition::Cloudy, current.condition);
+        assert_eq!(Some(String::from("overcast clouds")), current.summary);
+    }
 }
@@ -1,3 +1,7 @@
+use super::OwmUnit;
+use crate::weather::{Condition, HourlyPoint, Location, Observation, Report, Unit, Units};
+use crate::weather_api::Provider;
+use chrono::FixedOffset;
 use failure::Fail;
 use serde_derive::Deserialize;
 use std::fmt;
@@ -22,6 +26,7 @@ pub struct Current {
     coord: Option<Coord>,
     weather: Option<Vec<Weather>>,
     // internal
+    #[allow(dead_code)]
     base: Option<String>,
     main: Option<Main>,
     visibility: Option<i32>,
@@ -31,9 +36,13 @@ pub struct Current {
     snow: Option<Snow>,
     dt: Option<i64>,
     sys: Option<Sys>,
+    /// Offset from UTC, in seconds.
+    timezone: Option<i32>,
+    #[allow(dead_code)]
     id: Option<i64>,
     name: Option<String>,
     // internal
+    #[allow(dead_code)]
     cod: Option<i32>,
 }
 
@@ -48,8 +57,9 @@ pub struct Coord {
 #[serde(deny_unknown_fields)]
 pub struct Weather {
     id: Option<i32>,
-    description: Option<String>,
+    pub(super) description: Option<String>,
     icon: Option<Icon>,
+    #[allow(dead_code)]
     main: Option<String>,
 }
 
@@ -72,13 +82,19 @@ impl FromStr for Icon {
 #[serde(deny_unknown_fields)]
 pub struct Main {
     temp: Option<f64>,
+    feels_like: Option<f64>,
     // internal
+    #[allow(dead_code)]
     temp_kf: Option<f64>,
     pressure: Option<f64>,
     humidity: Option<f64>,
+    #[allow(dead_code)]
     temp_min: Option<f64>,
+    #[allow(dead_code)]
     temp_max: Option<f64>,
+    #[allow(dead_code)]
     sea_level: Option<f64>,
+    #[allow(dead_code)]
     grnd_level: Option<f64>,
 }
 
@@ -99,6 +115,8 @@ pub struct Clouds {
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Rain {
+    #[serde(rename = "1h")]
+    one_h: Option<f64>,
     #[serde(rename = "3h")]
     three_h: Option<f64>,
 }
@@ -106,6 +124,8 @@ pub struct Rain {
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Snow {
+    #[serde(rename = "1h")]
+    one_h: Option<f64>,
     #[serde(rename = "3h")]
     three_h: Option<f64>,
 }
@@ -115,10 +135,13 @@ pub struct Snow {
 pub struct Sys {
     #[serde(rename = "type")]
     // internal
+    #[allow(dead_code)]
     sys_type: Option<i32>,
     // internal
+    #[allow(dead_code)]
     id: Option<i32>,
     // internal
+    #[allow(dead_code)]
     message: Option<f64>,
     country: Option<String>,
     sunrise: Option<i64>,
@@ -129,11 +152,14 @@ pub struct Sys {
 #[serde(deny_unknown_fields)]
 pub struct Forecast {
     city: Option<ForecastCity>,
+    #[allow(dead_code)]
     cnt: Option<i32>,
     // internal
+    #[allow(dead_code)]
     cod: Option<String>,
     list: Option<Vec<ForecastList>>,
     // internal
+    #[allow(dead_code)]
     message: Option<f64>,
 }
 
@@ -142,9 +168,17 @@ pub struct Forecast {
 pub struct ForecastCity {
     coord: Option<Coord>,
     country: Option<String>,
+    #[allow(dead_code)]
     id: Option<i32>,
     name: Option<String>,
+    #[allow(dead_code)]
     population: Option<i32>,
+    #[allow(dead_code)]
+    sunrise: Option<i64>,
+    #[allow(dead_code)]
+    sunset: Option<i64>,
+    /// Offset from UTC, in seconds.
+    timezone: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -152,18 +186,181 @@ pub struct ForecastCity {
 pub struct ForecastList {
     clouds: Option<Clouds>,
     dt: Option<i64>,
+    #[allow(dead_code)]
     dt_txt: Option<String>,
     main: Option<Main>,
     // not documented
+    #[allow(dead_code)]
     sys: Option<ForecastSys>,
     rain: Option<Rain>,
     snow: Option<Snow>,
     weather: Option<Vec<Weather>>,
     wind: Option<Wind>,
+    #[allow(dead_code)]
+    visibility: Option<i32>,
+    /// Probability of precipitation.
+    pop: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct ForecastSys {
+    #[allow(dead_code)]
     pod: Option<String>,
 }
+
+impl Current {
+    pub fn into_report(self, unit: Option<OwmUnit>) -> Report {
+        let units = Units::from(unit);
+        let mut report = Report::new(Provider::Owm, units);
+        let sys = self.sys;
+
+        report.location = Location {
+            name: self.name,
+            country: sys.as_ref().and_then(|sys| sys.country.clone()),
+            latitude: self.coord.as_ref().map(|coord| coord.lat),
+            longitude: self.coord.as_ref().map(|coord| coord.lon),
+            timezone: None,
+            utc_offset: self.timezone.and_then(FixedOffset::east_opt),
+        };
+
+        let mut observation = Observation::new(self.dt.unwrap_or_default());
+        let weather = self.weather.as_ref().and_then(|weather| weather.first());
+        observation.summary = weather.and_then(|weather| weather.description.clone());
+        observation.condition = weather.map_or(Condition::Unknown, Weather::condition);
+        if let Some(main) = self.main {
+            observation.temperature = units.temperature.of(main.temp);
+            observation.apparent_temperature = units.temperature.of(main.feels_like);
+            observation.pressure = units.pressure.of(main.pressure);
+            observation.humidity = main.humidity.map(percent);
+        }
+        if let Some(wind) = self.wind {
+            observation.wind_speed = units.speed.of(wind.speed);
+            observation.wind_gust = units.speed.of(wind.gust);
+            observation.wind_bearing = wind.deg;
+        }
+        observation.cloud_cover = self
+            .clouds
+            .and_then(|clouds| clouds.all)
+            .map(f64::from)
+            .map(percent);
+        observation.visibility = units
+            .distance
+            .of(self.visibility.map(|metres| f64::from(metres) / 1000.));
+        observation.precip_intensity = units
+            .precipitation
+            .of(precip_intensity(&self.rain, &self.snow));
+        if let Some(sys) = sys {
+            observation.sunrise = sys.sunrise;
+            observation.sunset = sys.sunset;
+        }
+
+        report.current = Some(observation);
+        report
+    }
+}
+
+impl Forecast {
+    pub fn into_report(self, unit: Option<OwmUnit>) -> Report {
+        let units = Units::from(unit);
+        let mut report = Report::new(Provider::Owm, units);
+
+        if let Some(city) = self.city {
+            report.location = Location {
+                name: city.name,
+                country: city.country,
+                latitude: city.coord.as_ref().map(|coord| coord.lat),
+                longitude: city.coord.as_ref().map(|coord| coord.lon),
+                timezone: None,
+                utc_offset: city.timezone.and_then(FixedOffset::east_opt),
+            };
+        }
+
+        report.hourly = self
+            .list
+            .unwrap_or_default()
+            .into_iter()
+            .map(|list| list.into_hourly(units))
+            .collect();
+
+        report
+    }
+}
+
+impl ForecastList {
+    fn into_hourly(self, units: Units) -> HourlyPoint {
+        let mut point = HourlyPoint::new(self.dt.unwrap_or_default());
+        let weather = self.weather.as_ref().and_then(|weather| weather.first());
+        point.summary = weather.and_then(|weather| weather.description.clone());
+        point.condition = weather.map_or(Condition::Unknown, Weather::condition);
+        if let Some(main) = self.main {
+            point.temperature = units.temperature.of(main.temp);
+            point.apparent_temperature = units.temperature.of(main.feels_like);
+            point.pressure = units.pressure.of(main.pressure);
+            point.humidity = main.humidity.map(percent);
+        }
+        if let Some(wind) = self.wind {
+            point.wind_speed = units.speed.of(wind.speed);
+            point.wind_bearing = wind.deg;
+        }
+        point.cloud_cover = self
+            .clouds
+            .and_then(|clouds| clouds.all)
+            .map(f64::from)
+            .map(percent);
+        point.precip_intensity = units
+            .precipitation
+            .of(precip_intensity(&self.rain, &self.snow));
+        point.precip_probability = self.pop;
+
+        point
+    }
+}
+
+impl Weather {
+    /// Map OWM's condition codes onto a [`Condition`].
+    ///
+    /// See <https://openweathermap.org/weather-conditions>.
+    pub(super) fn condition(&self) -> Condition {
+        let night = self
+            .icon
+            .as_ref()
+            .is_some_and(|Icon(icon)| icon.ends_with('n'));
+
+        match self.id.unwrap_or_default() {
+            200..=299 => Condition::Thunderstorm,
+            300..=399 => Condition::Rain,
+            511 | 611..=616 => Condition::Sleet,
+            500..=599 => Condition::Rain,
+            600..=699 => Condition::Snow,
+            771 | 781 => Condition::Wind,
+            700..=799 => Condition::Fog,
+            800 if night => Condition::ClearNight,
+            800 => Condition::ClearDay,
+            801 | 802 if night => Condition::PartlyCloudyNight,
+            801 | 802 => Condition::PartlyCloudyDay,
+            803 | 804 => Condition::Cloudy,
+            _ => Condition::Unknown,
+        }
+    }
+}
+
+pub(super) fn percent(value: f64) -> f64 {
+    value / 100.
+}
+
+/// OWM reports the volume that fell over the last hour or three; turn that into an hourly rate.
+fn precip_intensity(rain: &Option<Rain>, snow: &Option<Snow>) -> Option<f64> {
+    let hourly = |one_h: Option<f64>, three_h: Option<f64>| one_h.or(three_h.map(|v| v / 3.));
+    let rain = rain
+        .as_ref()
+        .and_then(|rain| hourly(rain.one_h, rain.three_h));
+    let snow = snow
+        .as_ref()
+        .and_then(|snow| hourly(snow.one_h, snow.three_h));
+
+    match (rain, snow) {
+        (None, None) => None,
+        (rain, snow) => Some(rain.unwrap_or_default() + snow.unwrap_or_default()),
+    }
+}
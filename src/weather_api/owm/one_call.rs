@@ -0,0 +1,355 @@
+//! Models for the [One Call API](https://openweathermap.org/api/one-call-3).
+
+use super::models::{percent, Weather};
+use super::OwmUnit;
+use crate::weather::{
+    self, Condition, DailySummary, HourlyPoint, Location, Observation, Report, Unit, Units,
+};
+use crate::weather_api::Provider;
+use serde_derive::Deserialize;
+
+/// Everything for a location in one response: `/onecall`.
+#[derive(Debug, Deserialize)]
+pub struct OneCall {
+    lat: f64,
+    lon: f64,
+    timezone: String,
+    /// Offset from UTC, in seconds.
+    #[allow(dead_code)]
+    timezone_offset: i32,
+    current: Option<CurrentData>,
+    #[allow(dead_code)]
+    minutely: Option<Vec<Minutely>>,
+    hourly: Option<Vec<Hourly>>,
+    daily: Option<Vec<Daily>>,
+    alerts: Option<Vec<Alert>>,
+}
+
+/// Conditions at a point in time: `/onecall/timemachine`.
+#[derive(Debug, Deserialize)]
+pub struct TimeMachine {
+    lat: f64,
+    lon: f64,
+    timezone: String,
+    /// Offset from UTC, in seconds.
+    #[allow(dead_code)]
+    timezone_offset: i32,
+    data: Vec<CurrentData>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CurrentData {
+    dt: i64,
+    sunrise: Option<i64>,
+    sunset: Option<i64>,
+    temp: Option<f64>,
+    feels_like: Option<f64>,
+    pressure: Option<f64>,
+    humidity: Option<f64>,
+    dew_point: Option<f64>,
+    uvi: Option<f64>,
+    clouds: Option<f64>,
+    /// In metres.
+    visibility: Option<f64>,
+    wind_speed: Option<f64>,
+    wind_deg: Option<f64>,
+    wind_gust: Option<f64>,
+    #[serde(default)]
+    weather: Vec<Weather>,
+    rain: Option<Volume>,
+    snow: Option<Volume>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Minutely {
+    #[allow(dead_code)]
+    dt: i64,
+    /// In mm/h.
+    #[allow(dead_code)]
+    precipitation: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Hourly {
+    dt: i64,
+    temp: Option<f64>,
+    feels_like: Option<f64>,
+    pressure: Option<f64>,
+    humidity: Option<f64>,
+    #[allow(dead_code)]
+    dew_point: Option<f64>,
+    #[allow(dead_code)]
+    uvi: Option<f64>,
+    clouds: Option<f64>,
+    /// In metres.
+    #[allow(dead_code)]
+    visibility: Option<f64>,
+    wind_speed: Option<f64>,
+    wind_deg: Option<f64>,
+    #[allow(dead_code)]
+    wind_gust: Option<f64>,
+    #[serde(default)]
+    weather: Vec<Weather>,
+    /// Probability of precipitation.
+    pop: Option<f64>,
+    rain: Option<Volume>,
+    snow: Option<Volume>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Daily {
+    dt: i64,
+    sunrise: Option<i64>,
+    sunset: Option<i64>,
+    #[allow(dead_code)]
+    moonrise: Option<i64>,
+    #[allow(dead_code)]
+    moonset: Option<i64>,
+    #[allow(dead_code)]
+    moon_phase: Option<f64>,
+    summary: Option<String>,
+    temp: Option<DailyTemperature>,
+    feels_like: Option<DailyFeelsLike>,
+    pressure: Option<f64>,
+    humidity: Option<f64>,
+    #[allow(dead_code)]
+    dew_point: Option<f64>,
+    wind_speed: Option<f64>,
+    wind_deg: Option<f64>,
+    #[allow(dead_code)]
+    wind_gust: Option<f64>,
+    #[serde(default)]
+    weather: Vec<Weather>,
+    #[allow(dead_code)]
+    clouds: Option<f64>,
+    /// Probability of precipitation.
+    pop: Option<f64>,
+    /// Total for the day.
+    #[allow(dead_code)]
+    rain: Option<f64>,
+    /// Total for the day.
+    #[allow(dead_code)]
+    snow: Option<f64>,
+    uvi: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DailyTemperature {
+    #[allow(dead_code)]
+    morn: Option<f64>,
+    #[allow(dead_code)]
+    day: Option<f64>,
+    #[allow(dead_code)]
+    eve: Option<f64>,
+    #[allow(dead_code)]
+    night: Option<f64>,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DailyFeelsLike {
+    morn: Option<f64>,
+    day: Option<f64>,
+    eve: Option<f64>,
+    night: Option<f64>,
+}
+
+/// Precipitation over the last hour.
+#[derive(Debug, Deserialize)]
+pub struct Volume {
+    #[serde(rename = "1h")]
+    one_h: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Alert {
+    #[allow(dead_code)]
+    sender_name: Option<String>,
+    event: String,
+    start: i64,
+    end: i64,
+    description: String,
+    #[serde(default)]
+    #[allow(dead_code)]
+    tags: Vec<String>,
+}
+
+impl OneCall {
+    pub fn into_report(self, unit: Option<OwmUnit>) -> Report {
+        let units = Units::from(unit);
+        let mut report = Report::new(Provider::OwmOneCall, units);
+        report.location = location(self.lat, self.lon, self.timezone);
+
+        report.current = self.current.map(|current| current.into_observation(units));
+        report.hourly = self
+            .hourly
+            .unwrap_or_default()
+            .into_iter()
+            .map(|hourly| hourly.into_hourly(units))
+            .collect();
+        report.daily = self
+            .daily
+            .unwrap_or_default()
+            .into_iter()
+            .map(|daily| daily.into_daily(units))
+            .collect();
+        report.alerts = self
+            .alerts
+            .unwrap_or_default()
+            .into_iter()
+            .map(Alert::into_alert)
+            .collect();
+
+        report
+    }
+}
+
+impl TimeMachine {
+    /// The data point for the requested time becomes the current conditions.
+    pub fn into_report(self, unit: Option<OwmUnit>) -> Report {
+        let units = Units::from(unit);
+        let mut report = Report::new(Provider::OwmOneCall, units);
+        report.location = location(self.lat, self.lon, self.timezone);
+        report.current = self
+            .data
+            .into_iter()
+            .next()
+            .map(|current| current.into_observation(units));
+
+        report
+    }
+}
+
+impl CurrentData {
+    fn into_observation(self, units: Units) -> Observation {
+        Observation {
+            summary: summary(&self.weather),
+            condition: condition(&self.weather),
+            temperature: units.temperature.of(self.temp),
+            apparent_temperature: units.temperature.of(self.feels_like),
+            dew_point: units.temperature.of(self.dew_point),
+            humidity: self.humidity.map(percent),
+            pressure: units.pressure.of(self.pressure),
+            wind_speed: units.speed.of(self.wind_speed),
+            wind_gust: units.speed.of(self.wind_gust),
+            wind_bearing: self.wind_deg,
+            cloud_cover: self.clouds.map(percent),
+            visibility: units
+                .distance
+                .of(self.visibility.map(|metres| metres / 1000.)),
+            uv_index: self.uvi,
+            precip_intensity: units
+                .precipitation
+                .of(precip_intensity(&self.rain, &self.snow)),
+            sunrise: self.sunrise,
+            sunset: self.sunset,
+            ..Observation::new(self.dt)
+        }
+    }
+}
+
+impl Hourly {
+    fn into_hourly(self, units: Units) -> HourlyPoint {
+        HourlyPoint {
+            summary: summary(&self.weather),
+            condition: condition(&self.weather),
+            temperature: units.temperature.of(self.temp),
+            apparent_temperature: units.temperature.of(self.feels_like),
+            humidity: self.humidity.map(percent),
+            pressure: units.pressure.of(self.pressure),
+            wind_speed: units.speed.of(self.wind_speed),
+            wind_bearing: self.wind_deg,
+            cloud_cover: self.clouds.map(percent),
+            precip_intensity: units
+                .precipitation
+                .of(precip_intensity(&self.rain, &self.snow)),
+            precip_probability: self.pop,
+            ..HourlyPoint::new(self.dt)
+        }
+    }
+}
+
+impl Daily {
+    fn into_daily(self, units: Units) -> DailySummary {
+        let feels_like: Vec<f64> = self
+            .feels_like
+            .map(|f| vec![f.morn, f.day, f.eve, f.night])
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .collect();
+        let description = summary(&self.weather);
+
+        DailySummary {
+            summary: self.summary.or(description),
+            condition: condition(&self.weather),
+            temperature_high: units
+                .temperature
+                .of(self.temp.as_ref().and_then(|temp| temp.max)),
+            temperature_low: units
+                .temperature
+                .of(self.temp.as_ref().and_then(|temp| temp.min)),
+            apparent_temperature_high: units
+                .temperature
+                .of(feels_like.iter().cloned().reduce(f64::max)),
+            apparent_temperature_low: units
+                .temperature
+                .of(feels_like.iter().cloned().reduce(f64::min)),
+            humidity: self.humidity.map(percent),
+            pressure: units.pressure.of(self.pressure),
+            wind_speed: units.speed.of(self.wind_speed),
+            wind_bearing: self.wind_deg,
+            precip_probability: self.pop,
+            uv_index: self.uvi,
+            sunrise: self.sunrise,
+            sunset: self.sunset,
+            ..DailySummary::new(self.dt)
+        }
+    }
+}
+
+impl Alert {
+    fn into_alert(self) -> weather::Alert {
+        weather::Alert {
+            title: self.event,
+            description: self.description,
+            severity: None,
+            regions: Vec::new(),
+            issued: self.start,
+            expires: Some(self.end),
+            uri: None,
+        }
+    }
+}
+
+fn location(latitude: f64, longitude: f64, timezone: String) -> Location {
+    Location {
+        latitude: Some(latitude),
+        longitude: Some(longitude),
+        timezone: Some(timezone),
+        ..Default::default()
+    }
+}
+
+fn summary(weather: &[Weather]) -> Option<String> {
+    weather
+        .first()
+        .and_then(|weather| weather.description.clone())
+}
+
+fn condition(weather: &[Weather]) -> Condition {
+    weather
+        .first()
+        .map_or(Condition::Unknown, Weather::condition)
+}
+
+fn precip_intensity(rain: &Option<Volume>, snow: &Option<Volume>) -> Option<f64> {
+    let rain = rain.as_ref().and_then(|rain| rain.one_h);
+    let snow = snow.as_ref().and_then(|snow| snow.one_h);
+
+    match (rain, snow) {
+        (None, None) => None,
+        (rain, snow) => Some(rain.unwrap_or_default() + snow.unwrap_or_default()),
+    }
+}
@@ -0,0 +1,205 @@
+use super::darksky::{self, DarkSkyError, Forecast};
+use super::Config;
+use super::{ForecastApi, HistoricalApi, Provider, WeatherApi};
+use crate::config::{DarkSkyUnit, GenericWeatherUnit};
+use crate::weather::Report;
+use clap::ArgMatches;
+use url::Url;
+
+/// [Pirate Weather](https://pirateweather.net), which speaks DarkSky's format.
+#[derive(Debug, PartialEq)]
+pub struct PirateWeather<'a> {
+    pub key: &'a str,
+    pub coordinates: (f64, f64),
+    pub unit: Option<DarkSkyUnit>,
+}
+
+impl<'a> PirateWeather<'a> {
+    /// Historical data has its own host.
+    const TIME_MACHINE_URL: &'static str = "https://timemachine.pirateweather.net/forecast";
+}
+
+impl<'a> WeatherApi<'a> for PirateWeather<'a> {
+    const BASE_URL: &'static str = "https://api.pirateweather.net/forecast";
+    const PROVIDER: Provider = Provider::PirateWeather;
+    type Current = Forecast;
+    type ApiError = DarkSkyError;
+
+    fn new(config: &'a Config, m: &'a ArgMatches) -> Self {
+        let pirateweather_matches = m.subcommand_matches("pirateweather");
+
+        let pirateweather = config
+            .pirateweather
+            .as_ref()
+            .expect("Tried to create PirateWeather without api key.");
+
+        let coordinates = values_t!(m.values_of("coordinates"), f64)
+            .map(|coordinates| (coordinates[0], coordinates[1]))
+            .ok()
+            .or(config.coordinates)
+            .expect("tried creating pirate weather api without coordinates in config");
+
+        let unit = pirateweather_matches
+            .and_then(|pm| value_t!(pm.value_of("units"), DarkSkyUnit).ok())
+            .or_else(|| {
+                value_t!(m.value_of("units"), GenericWeatherUnit)
+                    .map(DarkSkyUnit::from)
+                    .ok()
+            })
+            .or(pirateweather.unit)
+            .or_else(|| config.unit.map(DarkSkyUnit::from));
+
+        Self {
+            key: &pirateweather.key,
+            coordinates,
+            unit,
+        }
+    }
+
+    fn url(&self) -> Url {
+        darksky::url(
+            Url::parse(Self::BASE_URL).unwrap(),
+            self.key,
+            self.coordinates,
+            self.unit,
+        )
+    }
+
+    fn current_url(&self) -> Url {
+        darksky::current_url(self.url())
+    }
+
+    fn api_key(&self) -> Option<&str> {
+        Some(self.key)
+    }
+
+    fn report(&self, current: Forecast) -> Report {
+        current.into_report(Provider::PirateWeather, self.unit)
+    }
+}
+
+impl<'a> ForecastApi<'a> for PirateWeather<'a> {
+    type Forecast = Forecast;
+
+    fn forecast_url(&self) -> Url {
+        self.url()
+    }
+
+    fn forecast_report(&self, forecast: Forecast) -> Report {
+        forecast.into_report(Provider::PirateWeather, self.unit)
+    }
+}
+
+impl<'a> HistoricalApi<'a> for PirateWeather<'a> {
+    type Historical = Forecast;
+
+    fn historical_url(&self, time: i64) -> Url {
+        let url = darksky::url(
+            Url::parse(Self::TIME_MACHINE_URL).unwrap(),
+            self.key,
+            self.coordinates,
+            self.unit,
+        );
+
+        darksky::historical_url(url, self.coordinates, time)
+    }
+
+    fn historical_report(&self, historical: Forecast, _time: i64) -> Report {
+        historical.into_report(Provider::PirateWeather, self.unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PirateWeatherConfig;
+    use crate::weather::{Severity, SpeedUnit, Temperature, TemperatureUnit};
+
+    #[test]
+    fn it_creates_new_pirateweather_with_its_own_key() {
+        let config = Config {
+            coordinates: Some((12.345, -54.321)),
+            pirateweather: Some(PirateWeatherConfig {
+                key: String::from("pirate_key"),
+                unit: Some(DarkSkyUnit::Ca),
+            }),
+            ..Default::default()
+        };
+
+        let matches = clap::App::new("name").get_matches_from(vec!["name"]);
+        let api = PirateWeather::new(&config, &matches);
+
+        assert_eq!(
+            Url::parse("https://api.pirateweather.net/forecast/pirate_key/12.345,-54.321?units=ca")
+                .unwrap(),
+            api.url()
+        );
+        assert_eq!(
+            Url::parse(
+                "https://timemachine.pirateweather.net/forecast/pirate_key/\
+                 12.345,-54.321,1542143061?units=ca"
+            )
+            .unwrap(),
+            api.historical_url(1_542_143_061)
+        );
+    }
+
+    #[test]
+    fn it_tolerates_pirate_weathers_extra_fields() {
+        let forecast: Forecast = serde_json::from_str(
+            r#"{
+                "latitude": 12.345,
+                "longitude": -54.321,
+                "timezone": "America/Winnipeg",
+                "offset": -6.0,
+                "elevation": 239,
+                "currently": {
+                    "time": 1542143061,
+                    "summary": "Snow",
+                    "icon": "snow",
+                    "precipType": "none",
+                    "temperature": -3.5,
+                    "smokeIntensity": 0.1
+                },
+                "daily": {
+                    "summary": "Snow",
+                    "icon": "snow",
+                    "data": [{ "time": 1542088800 }],
+                    "updateTime": 1542143000
+                },
+                "alerts": [{
+                    "title": "Blizzard Warning",
+                    "regions": ["Winnipeg"],
+                    "severity": "Severe",
+                    "time": 1542139200,
+                    "expires": 1542196800,
+                    "description": "Heavy snow and wind.",
+                    "uri": "https://example.com/alert"
+                }],
+                "flags": {
+                    "sources": ["ETOPO1", "gfs", "hrrr"],
+                    "sourceTimes": { "gfs": "2018-11-13 12Z" },
+                    "nearest-station": 0,
+                    "units": "uk",
+                    "version": "V2.0"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let api = PirateWeather {
+            key: "pirate_key",
+            coordinates: (12.345, -54.321),
+            unit: None,
+        };
+        let report = api.forecast_report(forecast);
+
+        assert_eq!(Provider::PirateWeather, report.provider);
+        assert_eq!(SpeedUnit::MilesPerHour, report.units.speed);
+        assert_eq!(
+            Some(Temperature::new(-3.5, TemperatureUnit::Celsius)),
+            report.current.unwrap().temperature
+        );
+        assert_eq!(None::<Severity>, report.alerts[0].severity);
+    }
+}